@@ -0,0 +1,81 @@
+//! Random ball and paddle placements through the collision maths and the ball speed limits:
+//! nothing may panic, and every result must be finite. `cargo +nightly fuzz run collision`
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use bevy::math::Vec2;
+use libfuzzer_sys::fuzz_target;
+use powder_game::pong::{components::PaddleShape, physics, resources::GameConfig};
+
+/// Furthest from the origin anything is placed, well past the court.
+const LIMIT: f32 = 1e5;
+
+#[derive(Debug, Arbitrary)]
+enum Shape { Classic, Flat, Convex, VShape }
+
+#[derive(Debug, Arbitrary)]
+struct Input {
+    shape: Shape,
+    ball: (f32, f32),
+    velocity: (f32, f32),
+    ball_half: f32,
+    paddle: (f32, f32),
+    paddle_half: (f32, f32),
+    facing_right: bool,
+    tick_rate: u16,
+}
+
+fn point((x, y): (f32, f32)) -> Option<Vec2> {
+    let p = Vec2::new(x, y);
+    (p.is_finite() && p.abs().max_element() <= LIMIT).then(|| p)
+}
+
+fn size((x, y): (f32, f32)) -> Option<Vec2> {
+    point((x, y)).filter(|s| s.min_element() > 0.)
+}
+
+fn assert_finite(v: Vec2, what: &str, input: &Input) {
+    assert!(v.is_finite(), "{} is {:?} for {:?}", what, v, input);
+}
+
+fuzz_target!(|input: Input| {
+    let (ball, velocity, paddle, paddle_half) = match (point(input.ball), point(input.velocity), point(input.paddle), size(input.paddle_half)) {
+        (Some(ball), Some(velocity), Some(paddle), Some(paddle_half)) => (ball, velocity, paddle, paddle_half),
+        _ => return,
+    };
+    let ball_half = match size((input.ball_half, input.ball_half)) {
+        Some(half) => half,
+        None => return,
+    };
+    let shape = match input.shape {
+        Shape::Classic => PaddleShape::Classic,
+        Shape::Flat => PaddleShape::Flat,
+        Shape::Convex => PaddleShape::Convex,
+        Shape::VShape => PaddleShape::VShape,
+    };
+    let facing = if input.facing_right { 1. } else { -1. };
+
+    if !physics::overlaps(ball, ball_half, paddle, paddle_half) {
+        return;
+    }
+    assert_finite(physics::contact_normal(ball, ball_half, paddle, paddle_half), "contact normal", &input);
+
+    let from_center = ball - paddle;
+    let normal = physics::paddle_normal(shape, from_center.y / paddle_half.y, facing);
+    assert_finite(normal, "paddle normal", &input);
+    assert_finite(physics::reflect(velocity, normal), "reflection", &input);
+
+    // ball_bounce scales this by the ball's speed, so it has to be a finite unit vector
+    if let Some(direction) = physics::bounce_direction(shape, velocity, from_center, paddle_half.y, facing) {
+        assert_finite(direction, "bounce direction", &input);
+        assert!((direction.length() - 1.).abs() < 1e-3, "bounce direction {:?} isn't a unit vector for {:?}", direction, input);
+    }
+
+    // every bounce ends with the ball held to the configured speed limits
+    let config = GameConfig { tick_rate: input.tick_rate.max(1) as u32, ..GameConfig::default() };
+    let limited = config.limit_ball_velocity(velocity);
+    assert_finite(limited, "limited velocity", &input);
+    let max_speed = config.per_tick(config.max_ball_speed);
+    assert!(limited.length() <= max_speed * (1. + 1e-3), "limited velocity {:?} is over {} for {:?}", limited, max_speed, input);
+});
@@ -0,0 +1,49 @@
+//! Runs the same seeded match twice and reports the first tick the two runs diverge on.
+//!
+//! `cargo run --bin determinism -- [--seed N] [--ticks N] [--threads]`
+//!
+//! With `--threads` the runs happen at the same time on two threads instead of one after the other.
+
+use std::{process, thread};
+
+use powder_game::pong::{resources::GameConfig, simulation::{Simulation, first_divergence, scripted_replay}};
+
+fn main() {
+    let mut seed = 0;
+    let mut ticks = 3600;
+    let mut threads = false;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--seed" => seed = args.next().and_then(|v| v.parse().ok()).unwrap_or(seed),
+            "--ticks" => ticks = args.next().and_then(|v| v.parse().ok()).unwrap_or(ticks),
+            "--threads" => threads = true,
+            other => {
+                eprintln!("unknown argument {}", other);
+                process::exit(2);
+            }
+        }
+    }
+
+    let config = GameConfig::default();
+    let replay = scripted_replay(&config, seed, ticks);
+    let (a, b) = if threads {
+        let first = {
+            let (config, replay) = (config.clone(), replay.clone());
+            thread::spawn(move || Simulation::checksums(config, replay))
+        };
+        let second = thread::spawn(move || Simulation::checksums(config, replay));
+        (first.join().unwrap(), second.join().unwrap())
+    } else {
+        (Simulation::checksums(config.clone(), replay.clone()), Simulation::checksums(config, replay))
+    };
+
+    match first_divergence(&a, &b) {
+        None => println!("seed {}: {} ticks identical", seed, a.len()),
+        Some(tick) => {
+            println!("seed {}: diverged on tick {} ({:?} vs {:?})", seed, tick, a.get(tick), b.get(tick));
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,4 @@
+//! Pong with a lot of balls. `pong::run` starts the game; `pong::components` and `pong::systems` are there for games
+//! built on its pieces.
+
+pub mod pong;
@@ -1,5 +1,3 @@
-mod pong;
-
 fn main() {
-    pong::run()
-}
\ No newline at end of file
+    powder_game::pong::run()
+}
@@ -1,162 +1,976 @@
+mod assist;
+mod ball_kind;
+mod breakout;
+mod collision;
+mod combo;
+mod config;
+mod controls;
+mod courts;
+mod crash;
+mod debug_overlay;
+mod event_log;
+mod exits;
+mod fog;
+mod frame_limit;
+mod gamepad;
+mod handheld;
+mod hazards;
+mod headless;
+mod idle;
+mod invisible;
+mod logging;
+mod menu;
+mod minimap;
+mod night;
+mod obstacles;
+mod portals;
+mod power_shot;
+mod prediction;
+mod profiler;
+mod rating;
+mod replay;
+mod replay_viewer;
+mod repro;
+mod rewind;
+mod sandbox;
+mod save;
+mod scoreboard;
+mod screens;
+mod serve;
+mod sets;
+mod sim_clock;
+mod speedometer;
+mod squash;
+mod stamina;
+mod touch;
+#[cfg(feature = "trace")]
+mod trace;
+mod transition;
+mod ui_scale;
+
 use std::time::Duration;
 
-use bevy::{PipelinedDefaultPlugins, app::prelude::*, asset::prelude::*, core::FixedTimestep, core::prelude::*, diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin}, ecs::prelude::*, ecs::schedule::ShouldRun, input::prelude::*, math::{Vec2, Vec3}, render2::{camera::OrthographicCameraBundle, color::Color, render_resource::{Extent3d, Texture, TextureFormat}, texture::Image, view::Visibility}, scene::prelude::*, sprite2::{*, self}, text::prelude::*, transform::prelude::*, window::prelude::*};
-use rand::Rng;
+use bevy::{DefaultPlugins, app::prelude::*, asset::prelude::*, core::prelude::*, ecs::prelude::*, ecs::schedule::{RunCriteria, ShouldRun}, input::{gamepad::{GamepadAxis, GamepadButton}, prelude::*}, log::LogPlugin, math::{Vec2, Vec3}, render::{camera::{OrthographicCameraBundle, OrthographicProjection}, color::Color, render_resource::{Extent3d, Texture, TextureFormat}, texture::Image, view::Visibility}, scene::prelude::*, sprite::{*, self}, text::prelude::*, transform::prelude::*, window::{prelude::*, WindowResized, WindowScaleFactorChanged}};
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg32;
+use serde::{Deserialize, Serialize};
+
+use ball_kind::BallKind;
+use breakout::LastHit;
+use collision::PaddleShape;
+pub use config::GameConfig;
+use event_log::{EventLog, GameEvent};
+use menu::{MenuCursor, MenuEvent};
+use power_shot::PowerShot;
+use screens::Winner;
+use squash::Squash;
+use stamina::Stamina;
+use transition::{Transition, TransitionPlugin};
 
-const FRAMERATE: f64 = 60.0;
-const TIMESTEP: f64 = 1.0 / FRAMERATE;
+/// Default physics ticks per second.
+const TICK_RATE: u32 = 60;
 
 const PADDLE_OFFSET: f32 = 50.0;
 const PADDLE_WIDTH: f32 = 12.5;
 const PADDLE_HEIGHT: f32 = 800.0;//75.0;
-const PADDLE_SPEED: f32 = 5.0 * (120.0 / FRAMERATE as f32);
-const BOT_PADDLE_SPEED: f32 = 5.0 * (120.0 / FRAMERATE as f32);
+const PADDLE_SPEED: f32 = 600.0;
+const BOT_PADDLE_SPEED: f32 = 600.0;
 const BALL_SIZE: f32 = 10.0;
-const BALL_SPEED:f32 = 7.0 * (120.0 / FRAMERATE as f32);
+const BALL_SPEED:f32 = 840.0;
 const BALL_LAUNCH_TIME:f32 = 10.0;
 const BALLS_AMOUNT:i64 = 100000;
+const SCORE_LIMIT:i32 = 10;
+const COURT_WIDTH: f32 = 1280.0;
+const COURT_HEIGHT: f32 = 720.0;
+const COURT_Z: f32 = -1.0;
+const GOAL_WIDTH: f32 = 4.0;
+const GOAL_Z: f32 = -0.5;
+const SCORE_Z: f32 = 5.;
+/// How far a splitter's two halves are turned away from each other, in radians.
+const SPLIT_ANGLE: f32 = 0.3;
 
+/// Runs the game with `config.ron`, overridden by the command line arguments. Edits to `config.ron`
+/// reload while running, with the arguments applied over them again.
 pub fn run() {
-    App::new()
-        .add_event::<ScoreEvent>()
-        .add_event::<ExitScreenEvent>()
-        .add_startup_system(setup.system())
-        .add_startup_stage("game_setup", 
-        SystemStage::parallel()
-                .with_system(spawn_paddles.system())
-                .with_system(spawn_background.system())
-            )
-        .add_system_set(SystemSet::new()
-            .with_run_criteria(FixedTimestep::step(TIMESTEP))
-            .with_system(update_velocity.system().label("movement"))
-            .with_system(ball_bounce.system().label("score").after("movement"))
-            .with_system(remove_off_screen_balls.system().after("score"))
-            .with_system(update_score.system().after("score")).label("physics"))
-        .add_system_set(SystemSet::new()
-            .with_run_criteria(should_spawn_balls.system())
-            .with_system(spawn_ball.system()))
-        .add_system_set(SystemSet::new()
-            .with_run_criteria(should_launch_ball.system())
-            .with_system(launch_ball.system()))
-        .add_system(player_input.system())
-        .add_system(paddle_boundaries.system())
-        .add_system(bot_ai.system())
-        .add_plugins(PipelinedDefaultPlugins)
-        .add_plugin(LogDiagnosticsPlugin::default())
-        .add_plugin(FrameTimeDiagnosticsPlugin::default())
-        .insert_resource(bevy::core_pipeline::ClearColor(Color::rgb(0.1, 0.1, 0.1)))
-        .run();
-}
-
-struct Player;
-struct Paddle;
-struct Bot;
-struct Ball;
-struct Velocity(Vec2);
+    let mut config = config::load().unwrap_or_else(|e| {
+        eprintln!("could not load {}: {}, using defaults", config::CONFIG_PATH, e);
+        GameConfig::default()
+    });
+    let (repro, args) = repro::ReproOptions::from_args(std::env::args().skip(1));
+    config.apply_args(args.iter().cloned());
+    let mut app = PongApp::with_config(config).handheld_prompt(handheld::detected());
+    app.file_args = Some(args);
+    if let Some(path) = repro.record {
+        app = app.record_input(path);
+    }
+    if let Some(path) = repro.play {
+        app = app.play_input(path);
+    }
+    app.run();
+}
+
+/// Runs the game with `config` in place of `config.ron`, which isn't read or watched.
+pub fn run_with_config(config: GameConfig) {
+    PongApp::with_config(config).run();
+}
+
+/// Bot paddle speed presets for `PongApp::bot_difficulty`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BotDifficulty { Easy, Normal, Hard }
+
+impl BotDifficulty {
+    const ALL: [BotDifficulty; 3] = [BotDifficulty::Easy, BotDifficulty::Normal, BotDifficulty::Hard];
+
+    /// The preset closest to a configured bot speed.
+    fn nearest(speed: f32) -> BotDifficulty {
+        let distance = |d: &BotDifficulty| (d.paddle_speed() - speed).abs();
+        Self::ALL.iter().copied()
+            .min_by(|a, b| distance(a).partial_cmp(&distance(b)).unwrap_or(std::cmp::Ordering::Equal))
+            .unwrap_or(BotDifficulty::Normal)
+    }
+
+    /// How far along the predicted path of a ball the bot aims: `0` follows the ball itself, `1`
+    /// waits where it will reach the paddle.
+    fn foresight(self) -> f32 {
+        match self {
+            BotDifficulty::Easy => 0.25,
+            BotDifficulty::Normal => 0.6,
+            BotDifficulty::Hard => 1.,
+        }
+    }
+
+    fn paddle_speed(self) -> f32 {
+        match self {
+            BotDifficulty::Easy => BOT_PADDLE_SPEED * 0.6,
+            BotDifficulty::Normal => BOT_PADDLE_SPEED,
+            BotDifficulty::Hard => BOT_PADDLE_SPEED * 1.5,
+        }
+    }
+}
+
+/// Sets up and runs the game from code:
+/// `PongApp::new().balls(3).bot_difficulty(BotDifficulty::Hard).score_limit(7).window_title("My Pong").run()`.
+pub struct PongApp {
+    config: GameConfig,
+    window_title: Option<String>,
+    repro: repro::ReproOptions,
+    handheld_prompt: bool,
+    /// Set by `run` when the config was read from `config.ron`, with the arguments applied over it;
+    /// only then is the file watched.
+    file_args: Option<Vec<String>>,
+}
+
+impl Default for PongApp {
+    fn default() -> Self {
+        Self::with_config(GameConfig::default())
+    }
+}
+
+impl PongApp {
+    /// Starts from the default config, ignoring `config.ron`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_config(config: GameConfig) -> Self {
+        Self { config, window_title: None, repro: Default::default(), handheld_prompt: false, file_args: None }
+    }
+
+    /// Balls served per match.
+    pub fn balls(mut self, amount: i64) -> Self {
+        self.config.balls_amount = amount;
+        self
+    }
+
+    pub fn bot_difficulty(mut self, difficulty: BotDifficulty) -> Self {
+        self.config.bot_paddle_speed = difficulty.paddle_speed();
+        self
+    }
+
+    /// Points needed to win a match.
+    pub fn score_limit(mut self, points: i32) -> Self {
+        self.config.score_limit = points;
+        self
+    }
+
+    pub fn window_title(mut self, title: impl Into<String>) -> Self {
+        self.window_title = Some(title.into());
+        self
+    }
+
+    /// Writes the config, seed and input of each finished match to `path`, for bug reports.
+    pub fn record_input(mut self, path: impl Into<String>) -> Self {
+        self.repro.record = Some(path.into());
+        self
+    }
+
+    /// Starts by playing back a file written by `record_input`, using its config in place of this one.
+    pub fn play_input(mut self, path: impl Into<String>) -> Self {
+        self.repro.play = Some(path.into());
+        self
+    }
+
+    /// Opens on the question whether to use the handheld preset, rather than the main menu, unless
+    /// the profile says it has been answered before.
+    pub fn handheld_prompt(mut self, ask: bool) -> Self {
+        self.handheld_prompt = ask;
+        self
+    }
+
+    /// Changes anything else in the config.
+    pub fn configure(mut self, f: impl FnOnce(&mut GameConfig)) -> Self {
+        f(&mut self.config);
+        self
+    }
+
+    pub fn run(self) {
+        let PongApp { mut config, window_title, repro, handheld_prompt, mut file_args } = self;
+        let profile = rating::load().unwrap_or_else(|e| {
+            eprintln!("could not load {}: {}, starting a new profile", rating::PROFILE_PATH, e);
+            rating::Profile::default()
+        });
+        let handheld_prompt = handheld_prompt && !profile.handheld_prompt_answered;
+        let mut initial_state = if handheld_prompt { GameState::HandheldPrompt } else { GameState::Menu };
+        let mut playback = None;
+        if let Some(path) = repro.play.as_deref() {
+            match repro::Repro::load(path) {
+                Ok(file) => {
+                    config = file.config;
+                    // the recorded config stands in for the file's
+                    file_args = None;
+                    playback = Some(file.replay);
+                    initial_state = GameState::Replay;
+                }
+                Err(e) => eprintln!("could not play back {}: {}", path, e),
+            }
+        }
+        let crash_reporter = crash::install();
+        let log_to_file = match config.log.file.as_deref() {
+            Some(path) => config.log.init_file_logging(path)
+                .map_err(|e| eprintln!("could not log to {}: {}", path, e))
+                .is_ok(),
+            None => false,
+        };
+        let log_settings = config.log.settings();
+        let log_diagnostics = config.log.diagnostics;
+        let scoreboard_window = config.scoreboard_window;
+
+        let mut app = App::new();
+        app
+            .insert_resource(WindowDescriptor {
+                vsync: config.vsync,
+                title: window_title.unwrap_or_else(|| WindowDescriptor::default().title),
+                ..Default::default()
+            })
+            .insert_resource(config)
+            .insert_resource(log_settings)
+            .insert_resource(config::ConfigOverrides { args: file_args.clone().unwrap_or_default(), ..Default::default() })
+            .insert_resource(crash_reporter)
+            .insert_resource(profile)
+            .insert_resource(rating::LastRatingChange::default())
+            .insert_resource(EventLog::default())
+            .insert_resource(MenuCursor::default())
+            .insert_resource(frame_limit::FrameLimiter::default())
+            .insert_resource(gamepad::ActivePad::default())
+            .insert_resource(MatchTick::default())
+            .insert_resource(replay::ReplayRecorder::default())
+            .insert_resource(replay::LastReplay(playback))
+            .insert_resource(repro::ReproRecording(repro.record))
+            .insert_resource(replay_viewer::ReplayViewer::default())
+            .insert_resource(power_shot::ScreenShake::default())
+            .insert_resource(combo::Combo::default())
+            .insert_resource(courts::CourtLayouts::load())
+            .insert_resource(speedometer::BallSpeeds::default())
+            .insert_resource(sets::Sets::default())
+            .insert_resource(serve::Serve::default())
+            .insert_resource(serve::FirstServer::default())
+            .insert_resource(sets::SwitchTimer::default())
+            .insert_resource(sandbox::SandboxControl::default())
+            .insert_resource(rewind::RewindBuffer::default())
+            .insert_resource(sim_clock::SimulationSpeed::default())
+            .insert_resource(sim_clock::SimClock::default())
+            .insert_resource(debug_overlay::DebugOverlay::default())
+            .insert_resource(profiler::SystemTimings::default())
+            .insert_resource(ui_scale::UiScale::default())
+            .insert_resource(touch::TouchDetected::default())
+            .insert_resource(idle::IdleTimer::default())
+            .insert_resource(save::PendingLoad::default())
+            .insert_resource(save::SaveFile::default())
+            .add_event::<ScoreEvent>()
+            .insert_resource(exits::ExitQueue::default())
+            .add_event::<BonusEvent>()
+            .add_event::<PaddleHitEvent>()
+            .add_event::<WallBounceEvent>()
+            .add_event::<ServeEvent>()
+            .add_event::<MenuEvent>()
+            .add_state(initial_state)
+            .add_startup_system(setup)
+            .add_startup_stage("game_setup", SystemStage::parallel()
+                    .with_system(minimap::spawn_minimap)
+                    .with_system(night::create_glow_texture)
+                    .with_system(spawn_court)
+                    .with_system(debug_overlay::spawn_debug_overlay)
+                )
+            .add_plugin(TransitionPlugin)
+            .add_system(menu::navigate_menu.label("menu_input"))
+            .add_system(screens::handle_menu_events.after("menu_input"))
+            .add_system(menu::update_menu_labels.after("menu_input"))
+            .add_system_set(SystemSet::on_enter(GameState::Menu)
+                .with_system(screens::spawn_menu))
+            .add_system_set(SystemSet::on_resume(GameState::Menu)
+                .with_system(screens::spawn_menu))
+            .add_system_set(SystemSet::on_pause(GameState::Menu)
+                .with_system(screens::despawn_screen))
+            .add_system_set(SystemSet::on_exit(GameState::Menu)
+                .with_system(screens::despawn_screen))
+            .add_system_set(SystemSet::on_enter(GameState::HandheldPrompt)
+                .with_system(handheld::spawn_handheld_prompt))
+            .add_system_set(SystemSet::on_exit(GameState::HandheldPrompt)
+                .with_system(screens::despawn_screen))
+            .add_system_set(SystemSet::on_enter(GameState::Paused)
+                .with_system(screens::spawn_pause_menu))
+            .add_system_set(SystemSet::on_resume(GameState::Paused)
+                .with_system(screens::spawn_pause_menu))
+            .add_system_set(SystemSet::on_pause(GameState::Paused)
+                .with_system(screens::despawn_screen))
+            .add_system_set(SystemSet::on_exit(GameState::Paused)
+                .with_system(screens::despawn_screen)
+                .with_system(gamepad::clear_disconnect_notice)
+                .with_system(idle::clear_idle_notice))
+            .add_system_set(SystemSet::on_enter(GameState::SwitchingSides)
+                .with_system(sets::spawn_switch_screen))
+            .add_system_set(SystemSet::on_update(GameState::SwitchingSides)
+                .with_system(sets::finish_switch))
+            .add_system_set(SystemSet::on_exit(GameState::SwitchingSides)
+                .with_system(screens::despawn_screen))
+            .add_system_set(SystemSet::on_enter(GameState::Settings)
+                .with_system(screens::spawn_settings))
+            .add_system_set(SystemSet::on_exit(GameState::Settings)
+                .with_system(screens::despawn_screen))
+            .add_system_set(SystemSet::on_enter(GameState::GameOver)
+                .with_system(rating::rate_match.label("rating"))
+                .with_system(screens::spawn_game_over.after("rating")))
+            .add_system_set(SystemSet::on_update(GameState::GameOver)
+                .with_system(screens::rematch_input))
+            .add_system_set(SystemSet::on_exit(GameState::GameOver)
+                .with_system(screens::despawn_screen))
+            .add_system_set(SystemSet::on_enter(GameState::Playing)
+                .with_system(spawn_paddles)
+                .with_system(spawn_background)
+                .with_system(power_shot::spawn_power_meter)
+                .with_system(stamina::spawn_stamina_bar)
+                .with_system(breakout::spawn_bricks)
+                .with_system(assist::spawn_assist_line)
+                .with_system(combo::reset_combo)
+                .with_system(combo::spawn_combo_text)
+                .with_system(speedometer::reset_ball_speeds)
+                .with_system(speedometer::spawn_speedometer)
+                .with_system(sets::spawn_deuce_text)
+                .with_system(serve::spawn_serve_indicator)
+                .with_system(fog::spawn_fog)
+                .with_system(night::spawn_darkness)
+                .with_system(touch::spawn_touch_zones)
+                .with_system(sim_clock::apply_tick_rate)
+                .with_system(sets::reset_sets)
+                .with_system(serve::reset_serve)
+                .with_system(replay::start_recording))
+            .add_system_set(SystemSet::on_exit(GameState::Playing)
+                .with_system(teardown_match)
+                .with_system(replay::finish_recording.label("finish_recording"))
+                .with_system(repro::save_repro.after("finish_recording")))
+            .add_system_set(SystemSet::on_enter(GameState::Sandbox)
+                .with_system(spawn_paddles)
+                .with_system(breakout::spawn_bricks)
+                .with_system(sandbox::reset_sandbox)
+                .with_system(sets::reset_sets)
+                .with_system(serve::reset_serve)
+                .with_system(sim_clock::apply_tick_rate)
+                .with_system(rewind::clear_rewind))
+            .add_system_set(SystemSet::on_update(GameState::Sandbox)
+                .with_system(player_input)
+                .with_system(sandbox::spawn_ball_at_cursor)
+                .with_system(sandbox::sandbox_keys)
+                .with_system(rewind::rewind)
+                .with_system(sim_clock::simulation_speed_keys)
+                .with_system(breakout::update_particles)
+                .with_system(hazards::sync_hazards)
+                .with_system(portals::sync_portals)
+                .with_system(obstacles::sync_obstacles)
+                .with_system(portals::swirl_portals))
+            .add_system_set(SystemSet::on_exit(GameState::Sandbox)
+                .with_system(teardown_match))
+            .add_system_set(SystemSet::on_enter(GameState::Replay)
+                .with_system(spawn_paddles)
+                .with_system(spawn_background)
+                .with_system(power_shot::spawn_power_meter)
+                .with_system(stamina::spawn_stamina_bar)
+                .with_system(breakout::spawn_bricks)
+                .with_system(combo::reset_combo)
+                .with_system(combo::spawn_combo_text)
+                .with_system(speedometer::reset_ball_speeds)
+                .with_system(speedometer::spawn_speedometer)
+                .with_system(sets::spawn_deuce_text)
+                .with_system(serve::spawn_serve_indicator)
+                .with_system(night::spawn_darkness)
+                .with_system(sim_clock::apply_tick_rate)
+                .with_system(sets::reset_sets)
+                .with_system(replay_viewer::begin_playback))
+            .add_system_set(SystemSet::on_update(GameState::Replay)
+                .with_system(replay_viewer::viewer_keys)
+                .with_system(replay_viewer::update_timeline)
+                .with_system(sim_clock::simulation_speed_keys)
+                .with_system(power_shot::update_power_meter)
+                .with_system(stamina::update_stamina_bar)
+                .with_system(combo::update_combo_text)
+                .with_system(speedometer::update_speedometer)
+                .with_system(sets::update_deuce_text)
+                .with_system(serve::update_serve_indicator)
+                .with_system(breakout::update_particles)
+                .with_system(hazards::sync_hazards)
+                .with_system(portals::sync_portals)
+                .with_system(obstacles::sync_obstacles)
+                .with_system(portals::swirl_portals))
+            .add_system_set(SystemSet::on_exit(GameState::Replay)
+                .with_system(teardown_match)
+                .with_system(replay_viewer::end_playback))
+            .add_system_set(SystemSet::on_enter(GameState::ReplayLoading)
+                .with_system(replay_viewer::restart_playback))
+            // everything that affects the simulation runs on the fixed tick so replays re-simulate exactly
+            .add_system_set(simulation_systems()
+                .with_run_criteria(sim_clock::fixed_tick.chain(run_if_playing).label("physics_tick")))
+            .add_system_set(serving_systems()
+                .with_run_criteria(RunCriteria::pipe("physics_tick", run_if_serving.system())))
+            .add_system_set(SystemSet::on_update(GameState::Playing)
+                .with_system(screens::pause_input)
+                .with_system(screens::auto_pause)
+                .with_system(player_input)
+                .with_system(sim_clock::simulation_speed_keys)
+                .with_system(power_shot::power_shot_input)
+                .with_system(power_shot::update_power_meter)
+                .with_system(stamina::update_stamina_bar)
+                .with_system(combo::update_combo_text)
+                .with_system(speedometer::update_speedometer)
+                .with_system(sets::update_deuce_text)
+                .with_system(serve::update_serve_indicator)
+                .with_system(breakout::update_particles)
+                .with_system(hazards::sync_hazards)
+                .with_system(portals::sync_portals)
+                .with_system(obstacles::sync_obstacles)
+                .with_system(assist::update_assist_line)
+                .with_system(fog::update_fog)
+                .with_system(touch::update_touch_zones)
+                .with_system(portals::swirl_portals)
+                .with_system(save::save_match)
+                .with_system(save::load_match))
+            .add_system(gamepad::track_gamepads)
+            .add_system(touch::detect_touch)
+            .add_system(idle::track_idle)
+            .add_system(minimap::update_minimap)
+            .add_system(cull_off_screen_balls)
+            .add_system(fit_camera_to_court)
+            .add_system(refresh_text_on_rescale)
+            .add_system(ui_scale::update_ui_scale)
+            .add_system(resize_court)
+            .add_system(minimap::place_minimap)
+            .add_system(power_shot::shake_camera)
+            .add_system(squash::animate_squash)
+            .add_system(invisible::flag_invisible_balls)
+            .add_system(invisible::flash_invisible_balls)
+            .add_system(night::follow_balls)
+            .add_system(night::update_darkness)
+            .add_system(size_paddle_sprites)
+            .add_system(size_ball_sprites)
+            .add_system(debug_overlay::toggle_debug_overlay)
+            .add_system(debug_overlay::count_spawns)
+            .add_system(debug_overlay::update_debug_overlay)
+            .add_system(config::watch_config.label("config"))
+            .add_system(config::apply_config.after("config"))
+            // its own stage so texts spawned during Update are scaled before they are first laid out
+            .add_stage_after(CoreStage::Update, "ui_scale", SystemStage::parallel()
+                    .with_system(ui_scale::scale_text)
+                    .with_system(ui_scale::reload_font)
+                )
+            // after Update's commands, so it never inserts onto a ball that went out this frame
+            .add_system_to_stage(CoreStage::PostUpdate, night::attach_glows)
+            .add_system_to_stage(CoreStage::Last, crash::update_crash_context)
+            .add_system_to_stage(CoreStage::Last, profiler::end_frame)
+            .add_system_to_stage(CoreStage::Last, frame_limit::limit_frame_rate)
+            .add_plugins_with(DefaultPlugins, |group| {
+                if log_to_file {
+                    group.disable::<LogPlugin>();
+                }
+                group
+            })
+            // everything outside the court is letterbox
+            .insert_resource(bevy::core_pipeline::ClearColor(Color::BLACK));
+        // needs the renderer DefaultPlugins sets up
+        if scoreboard_window {
+            app.add_plugin(scoreboard::ScoreboardPlugin);
+        }
+        #[cfg(feature = "diagnostics")]
+        {
+            use bevy::diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin};
+            app.add_plugin(FrameTimeDiagnosticsPlugin::default());
+            if log_diagnostics {
+                app.add_plugin(LogDiagnosticsPlugin::default());
+            }
+        }
+        #[cfg(not(feature = "diagnostics"))]
+        if log_diagnostics {
+            eprintln!("built without the diagnostics feature, ignoring log diagnostics");
+        }
+        #[cfg(feature = "trace")]
+        app.add_system_to_stage(CoreStage::Last, trace::capture_trace);
+        if file_args.is_some() {
+            app.insert_resource(config::ConfigWatcher::new());
+        }
+        app.run();
+    }
+}
+
+/// The public building blocks, for games reusing the pong physics and bot.
+pub mod components {
+    pub use super::{Ball, BallBundle, BallSize, Bot, Court, GoalZone, MatchEntity, Paddle, PaddleBundle, PaddleSize, Player, Score, ServeTimer, Velocity, Who};
+    pub use super::{ball_kind::BallKind, breakout::LastHit, collision::PaddleShape, power_shot::PowerShot, stamina::Stamina};
+}
+
+/// What happens in a match, for plugins adding sounds and effects without touching the physics.
+/// The physics tick sends them; read them with an `EventReader` from any system.
+pub mod events {
+    pub use super::{BonusEvent, ExitScreenEvent, PaddleHitEvent, ScoreEvent, ServeEvent, WallBounceEvent};
+    pub use super::exits::{ExitQueue, ExitReader};
+}
+
+/// Resources the systems read and write; `PongApp` inserts all of them.
+pub mod resources {
+    pub use super::{BallCount, BallSprite, GameConfig, GameRng, GameState, MatchTick, UiFont};
+    pub use super::{event_log::EventLog, profiler::SystemTimings, transition::Transition};
+}
+
+/// The collision maths the bounce systems are built on.
+pub mod physics {
+    pub use super::collision::{bounce_direction, contact_normal, limit_velocity, normalize_or, overlaps, paddle_normal, reflect, resolve_paddle_hit};
+}
+
+/// Playing replays back without a window, for tests and tools.
+pub mod simulation {
+    pub use super::headless::{Simulation, first_divergence, scripted_replay};
+    pub use super::replay::{InputChange, Replay, ReplayHeader, REPLAY_VERSION, config_hash};
+}
+
+/// The movement, collision, scoring and bot systems. Physics systems expect to run on a fixed tick.
+pub mod systems {
+    pub use super::{ball_bounce, bot_ai, launch_ball, paddle_boundaries, player_input, remove_off_screen_balls, spawn_ball, spawn_paddles, update_score, update_velocity};
+}
+
+#[derive(Component)]
+pub struct Player;
+#[derive(Component)]
+pub struct Paddle;
+/// Full width and height of a paddle, for collisions and its sprite. Starts at `GameConfig::paddle_size`
+/// and can be changed per paddle.
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct PaddleSize(pub Vec2);
+/// Width of a ball, for collisions and its sprite. Starts at its kind's size and can be changed per ball.
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct BallSize(pub f32);
+#[derive(Component)]
+pub struct Bot;
+#[derive(Component)]
+pub struct Ball;
+#[derive(Component)]
+pub struct Court;
+/// Marks the mouth of the goal on one side of the court.
+#[derive(Component)]
+pub struct GoalZone;
+/// Anything spawned for a match; despawned when leaving `GameState::Playing`.
+#[derive(Component)]
+pub struct MatchEntity;
+/// Pixels per physics tick.
+#[derive(Component)]
+pub struct Velocity(pub Vec2);
 
 #[derive(Default)]
-struct UiFont(Handle<Font>);
+pub struct UiFont(Handle<Font>);
 
+#[derive(Component)]
 struct PlayerText();
+#[derive(Component)]
 struct OpponentText();
-struct Score(Who, i32);
-struct LaunchTimer(Timer);
-struct BallCount(i32);
-struct BallSprite(PipelinedSpriteBundle);
+/// Points scored by balls going out on `Who`'s side.
+#[derive(Component)]
+pub struct Score(pub Who, pub i32);
+/// Counts down to a ball's serve. Removed once the ball is served, so balls already in play (split
+/// off a splitter, or loaded from a save) are left alone.
+#[derive(Component)]
+pub struct ServeTimer(pub Timer);
 
-#[derive(PartialEq)]
-enum Who { PLAYER, OPPONENT }
+impl ServeTimer {
+    pub fn new(config: &GameConfig) -> Self {
+        Self(Timer::from_seconds(config.ball_launch_time, false))
+    }
+}
+
+pub struct BallCount(pub i32);
+pub struct GameRng(Pcg32);
 
-struct ScoreEvent(Who);
-struct ExitScreenEvent(Entity, Who); 
+/// The plain white texture every sprite in the game is drawn with, sized and tinted per sprite.
+pub struct BallSprite {
+    texture: Handle<Image>,
+    color: Color,
+}
+
+impl BallSprite {
+    /// A sprite in the base color.
+    pub fn bundle(&self, size: Vec2, translation: Vec3) -> SpriteBundle {
+        self.tinted(self.color, size, translation)
+    }
+
+    pub fn tinted(&self, color: Color, size: Vec2, translation: Vec3) -> SpriteBundle {
+        SpriteBundle {
+            sprite: sprite::Sprite {
+                color,
+                custom_size: Some(size),
+                ..Default::default()
+            },
+            texture: self.texture.clone(),
+            transform: Transform::from_translation(translation),
+            ..Default::default()
+        }
+    }
+}
+
+/// Everything a ball needs to take part in a match.
+#[derive(Bundle)]
+pub struct BallBundle {
+    #[bundle]
+    pub sprite: SpriteBundle,
+    pub velocity: Velocity,
+    pub kind: BallKind,
+    pub size: BallSize,
+    pub last_hit: LastHit,
+    pub portal_cooldown: portals::PortalCooldown,
+    pub squash: Squash,
+    pub ball: Ball,
+    pub match_entity: MatchEntity,
+}
+
+impl BallBundle {
+    /// A normal ball of `size` pixels across at `position`, moving `velocity` pixels per tick.
+    pub fn new(sprite: &BallSprite, position: Vec2, velocity: Vec2, size: f32) -> Self {
+        Self {
+            sprite: sprite.bundle(Vec2::splat(size), position.extend(0.)),
+            velocity: Velocity(velocity),
+            kind: BallKind::Normal,
+            size: BallSize(size),
+            last_hit: LastHit::default(),
+            portal_cooldown: portals::PortalCooldown::default(),
+            squash: Squash::default(),
+            ball: Ball,
+            match_entity: MatchEntity,
+        }
+    }
+
+    pub fn with_kind(mut self, kind: BallKind) -> Self {
+        self.kind = kind;
+        self
+    }
+}
+
+/// What both paddles have in common; `Player` or `Bot` is added on top.
+#[derive(Bundle)]
+pub struct PaddleBundle {
+    #[bundle]
+    pub sprite: SpriteBundle,
+    pub velocity: Velocity,
+    pub shape: PaddleShape,
+    pub size: PaddleSize,
+    pub squash: Squash,
+    pub paddle: Paddle,
+    pub match_entity: MatchEntity,
+}
+
+impl PaddleBundle {
+    /// A still, classic paddle of `size` on `side`'s end of the court, `config.paddle_offset` in from the edge.
+    pub fn new(sprite: &BallSprite, config: &GameConfig, side: Who, size: Vec2) -> Self {
+        let x = config.court_half().x - config.paddle_offset;
+        let x = if side == Who::PLAYER { -x } else { x };
+        Self {
+            sprite: sprite.bundle(size, Vec3::new(x, 0., 0.)),
+            velocity: Velocity(Vec2::ZERO),
+            shape: PaddleShape::Classic,
+            size: PaddleSize(size),
+            squash: Squash::default(),
+            paddle: Paddle,
+            match_entity: MatchEntity,
+        }
+    }
+
+    pub fn with_shape(mut self, shape: PaddleShape) -> Self {
+        self.shape = shape;
+        self
+    }
+}
+
+/// Physics ticks since the match started.
+#[derive(Default)]
+pub struct MatchTick(pub u32);
+
+#[derive(PartialEq, Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum Who { PLAYER, OPPONENT }
+
+impl Default for Who {
+    fn default() -> Self {
+        Who::PLAYER
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GameState { Menu, HandheldPrompt, Playing, Paused, SwitchingSides, Settings, GameOver, Sandbox, Replay, ReplayLoading }
+
+/// `scorer` scored a point with `ball`, which went out at `position` moving at `velocity`.
+/// Velocities in events are in pixels per tick.
+pub struct ScoreEvent {
+    pub scorer: Who,
+    pub ball: Entity,
+    pub position: Vec2,
+    pub velocity: Vec2,
+}
+/// `ball` came off `paddle`, belonging to `hitter`, at `position` with its new `velocity`.
+pub struct PaddleHitEvent {
+    pub ball: Entity,
+    pub paddle: Entity,
+    pub hitter: Who,
+    pub position: Vec2,
+    pub velocity: Vec2,
+}
+/// `ball` bounced off the top or bottom wall, or the wall either side of a goal, at `position`.
+pub struct WallBounceEvent {
+    pub ball: Entity,
+    pub position: Vec2,
+    pub velocity: Vec2,
+}
+/// `ball` was served from `position`. `server` is only known with serve turns on.
+pub struct ServeEvent {
+    pub ball: Entity,
+    pub server: Option<Who>,
+    pub position: Vec2,
+    pub velocity: Vec2,
+}
+/// A ball went out on `Who`'s side. Queued in `ExitQueue` rather than sent as an event.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ExitScreenEvent(pub Entity, pub Who);
+/// Extra points for `Who`, on top of what balls going out score.
+pub struct BonusEvent(pub Who, pub i32);
 struct PixelTexture(Texture);
 
 fn setup(
     mut commands: Commands, 
     mut textures: ResMut<Assets<Image>>,
     asset_server : Res<AssetServer>,
+    config : Res<GameConfig>,
 ) {
     commands.spawn_bundle(OrthographicCameraBundle::new_2d());
 
     let image: Handle<Image> = asset_server.load("pixel.png");
 
-    let font: Handle<Font> = asset_server.load("Consola.ttf");
+    let font: Handle<Font> = asset_server.load(config.font.as_str());
     commands.insert_resource(UiFont(font));
     commands.insert_resource(BallCount(Default::default()));
-    commands.insert_resource(LaunchTimer(Timer::new(Duration::from_secs_f32(BALL_LAUNCH_TIME), false)));
-    commands.insert_resource(BallSprite(PipelinedSpriteBundle {
-                sprite: sprite2::Sprite {
-                    color : Color::WHITE,
-                    custom_size: Some(Vec2::new(BALL_SIZE, BALL_SIZE)),
-                    ..Default::default()
-                },
-                texture: image,
-                visibility: Visibility { is_visible: true },
-                ..Default::default()
-            }));
+    commands.insert_resource(GameRng(Pcg32::from_entropy()));
+    commands.insert_resource(BallSprite {
+        texture: image,
+        color: Color::WHITE,
+    });
 }
 
 fn spawn_background(
     mut commands: Commands,
     material: Res<BallSprite>,
     font: Res<UiFont>,
-    windows: Res<Windows>
+    config: Res<GameConfig>,
 ) {
-    let window = windows.get_primary().unwrap();
+    let court = config.court_half();
 
-    let mut mat = material.0.clone();
-    mat.sprite.custom_size = Some(Vec2::new(2., window.height()));
+    commands.spawn_bundle(material.bundle(Vec2::new(2., court.y * 2.), Vec3::ZERO)).insert(MatchEntity);
 
-    commands.spawn_bundle(mat);
+    let text_y = -court.y;
+    let text_x = court.x / 2.;
+    // scores are keyed by the side balls go out on, so Who::PLAYER counts the bot's points
+    add_text(&mut commands, Vec2::new(text_x, text_y), &font, Who::PLAYER, config.bot_handicap.head_start, PlayerText);
+    add_text(&mut commands, Vec2::new(-text_x, text_y), &font, Who::OPPONENT, config.player_handicap.head_start, OpponentText);
+}
 
-    let text_y = window.height() / 2. * -1.;
-    let text_x = window.width() / 4.;
-    add_text(&mut commands, Vec2::new(text_x, text_y), &font, Who::PLAYER, PlayerText);
-    add_text(&mut commands, Vec2::new(-text_x, text_y), &font, Who::OPPONENT, OpponentText);
+/// The physics tick, in order. `PongApp` runs it on the fixed timestep and `Simulation` once per step.
+fn simulation_systems() -> SystemSet {
+    SystemSet::new()
+        .with_system(replay_viewer::replay_input.label("input").before("movement"))
+        .with_system(replay::record_input.after("input").before("movement"))
+        .with_system(bot_ai.before("movement"))
+        .with_system(hazards::apply_hazard_forces.after("launch").before("movement"))
+        .with_system(obstacles::patrol_obstacles.before("movement"))
+        .with_system(power_shot::charge_power_shot.after("input").before("movement"))
+        .with_system(stamina::apply_stamina.after("input").before("movement"))
+        .with_system(update_velocity.label("movement"))
+        .with_system(paddle_boundaries.label("boundaries").after("movement"))
+        .with_system(portals::teleport_balls.label("portals").after("movement"))
+        .with_system(obstacles::bounce_off_obstacles.label("obstacles").after("portals"))
+        .with_system(ball_bounce.label("score").after("boundaries").after("obstacles"))
+        .with_system(breakout::break_bricks.label("bricks").after("score"))
+        .with_system(remove_off_screen_balls.label("despawn").after("score"))
+        .with_system(sets::switch_sides.label("sets").after("points").after("despawn"))
+        .with_system(stamina::update_stamina.after("movement"))
+        .with_system(update_score.label("points").after("bricks"))
+        .with_system(exits::retire_exits.after("points").after("despawn").after("sets"))
+        .with_system(replay::record_scores.after("points"))
+        .with_system(combo::score_combos.after("points"))
+        .with_system(serve::alternate_serve.after("points"))
+        .with_system(speedometer::measure_ball_speeds.after("score"))
+        .with_system(rewind::record_snapshot.after("bricks"))
+        .with_system(advance_tick.after("score").after("points"))
+        .label("physics")
 }
 
-fn should_launch_ball(
-    mut timer: ResMut<LaunchTimer>,
-    time : Res<Time>,
+/// Serving new balls, part of the physics tick everywhere but the sandbox.
+fn serving_systems() -> SystemSet {
+    SystemSet::new()
+        .with_system(launch_ball.label("launch").before("movement"))
+        .with_system(spawn_ball.after("despawn").after("sets"))
+        .label("physics")
+}
+
+fn run_if_playing(
+    In(should_run): In<ShouldRun>,
+    state : Res<State<GameState>>,
+    mut sandbox : ResMut<sandbox::SandboxControl>,
 ) -> ShouldRun {
-    match timer.0.tick(time.delta()).just_finished() {
-        true => ShouldRun::Yes,
-        false => ShouldRun::No
+    match state.current() {
+        GameState::Playing | GameState::Replay => should_run,
+        GameState::Sandbox => sandbox.should_run(should_run),
+        _ => ShouldRun::No
     }
 }
 
-fn launch_ball(
-    mut ball: Query<&mut Velocity, With<Ball>>
+/// Sandbox balls are only ever placed by hand, and keep the velocity they were dragged out with.
+fn run_if_serving(
+    In(should_run): In<ShouldRun>,
+    state : Res<State<GameState>>,
+) -> ShouldRun {
+    match state.current() {
+        GameState::Sandbox => ShouldRun::No,
+        _ => should_run,
+    }
+}
+
+fn spawn_court(
+    mut commands: Commands,
+    sprite: Res<BallSprite>,
+    config: Res<GameConfig>,
 ) {
-    let mut random = rand::thread_rng();
-    for mut b in ball.iter_mut() {
-        let x = (random.gen::<f32>() - 0.5) * 2.;
-        let y = random.gen::<f32>() - 0.5;
+    let (r, g, b) = config.court_theme.court;
+    let court = sprite.tinted(Color::rgb(r, g, b), config.court_half() * 2., Vec3::new(0., 0., COURT_Z));
+    commands.spawn_bundle(court).insert(Court);
 
-        b.0 = Vec2::new(x,y).normalize() * BALL_SPEED;
+    for side in [-1., 1.] {
+        let (r, g, b) = config.court_theme.goal;
+        let goal = sprite.tinted(
+            Color::rgb(r, g, b),
+            Vec2::new(GOAL_WIDTH, config.goal_half() * 2.),
+            Vec3::new(side * config.court_half().x, 0., GOAL_Z));
+        commands.spawn_bundle(goal).insert(GoalZone);
     }
 }
 
-fn bot_ai(
-    mut bot_query : Query<(&Transform, &mut Velocity), With<Bot>>,
-    ball_query : Query<&Transform, With<Ball>>
+fn resize_court(
+    mut court : Query<&mut sprite::Sprite, (With<Court>, Without<GoalZone>)>,
+    mut goals : Query<(&mut sprite::Sprite, &mut Transform), With<GoalZone>>,
+    config : Res<GameConfig>,
 ) {
+    if !config.is_changed() {
+        return;
+    }
+    let theme = &config.court_theme;
+    for mut sprite in court.iter_mut() {
+        sprite.custom_size = Some(config.court_half() * 2.);
+        sprite.color = Color::rgb(theme.court.0, theme.court.1, theme.court.2);
+    }
+    for (mut sprite, mut t) in goals.iter_mut() {
+        sprite.custom_size = Some(Vec2::new(GOAL_WIDTH, config.goal_half() * 2.));
+        sprite.color = Color::rgb(theme.goal.0, theme.goal.1, theme.goal.2);
+        t.translation.x = t.translation.x.signum() * config.court_half().x;
+    }
+}
+
+pub fn launch_ball(
+    mut commands : Commands,
+    mut balls : Query<(Entity, &Transform, &mut Velocity, &BallKind, &mut ServeTimer), With<Ball>>,
+    mut serves : EventWriter<ServeEvent>,
+    mut rng : ResMut<GameRng>,
+    mut log : ResMut<EventLog>,
+    clock : Res<sim_clock::SimClock>,
+    serve : Res<serve::Serve>,
+    sets : Res<sets::Sets>,
+    config : Res<GameConfig>,
+    time : Res<Time>,
+) {
+    let step = Duration::from_secs_f64(clock.timestep);
+    let mut served = false;
+    for (e, t, mut b, kind, mut timer) in balls.iter_mut() {
+        if !timer.0.tick(step).just_finished() {
+            continue;
+        }
+        let x = (rng.0.gen::<f32>() - 0.5) * 2.;
+        let y = rng.0.gen::<f32>() - 0.5;
+        // with serve turns the ball heads for the receiver
+        let x = if config.serve_every > 0 { x.abs() * sets.side_of(serve.receiver()) } else { x };
+
+        // both rolls landing on 0 would leave no direction to serve in
+        b.0 = collision::normalize_or(Vec2::new(x,y), Vec2::X) * kind.speed(&config);
+        commands.entity(e).remove::<ServeTimer>();
+        served = true;
+        let server = if config.serve_every > 0 { Some(serve.server) } else { None };
+        serves.send(ServeEvent { ball: e, server, position: t.translation.truncate(), velocity: b.0 });
+    }
+
+    if served {
+        log.push(time.seconds_since_startup(), GameEvent::Serve);
+    }
+}
+
+/// Moves the bot towards where the closest ball will reach it, looking as far ahead along the
+/// predicted path as its difficulty allows.
+pub fn bot_ai(
+    mut bot_query : Query<(&Transform, &PaddleSize, &mut Velocity), With<Bot>>,
+    ball_query : Query<(&Transform, &Velocity, &BallSize), (With<Ball>, Without<Bot>)>,
+    mut timings : ResMut<profiler::SystemTimings>,
+    config : Res<GameConfig>,
+) {
+    let _timed = timings.time("bot_ai");
+    let foresight = BotDifficulty::nearest(config.bot_paddle_speed).foresight();
 
     // Get the closest ball to the paddle
-    for (t, mut v) in bot_query.iter_mut() {
+    for (t, size, mut v) in bot_query.iter_mut() {
         let mut ball : Vec3 = Vec3::ONE * f32::MAX;
+        let mut path_end = ball.y;
         let mut dist = f32::MAX;
-        for b in ball_query.iter() {
+        for (b, b_v, b_size) in ball_query.iter() {
             let b_dist = (b.translation - t.translation).length();
             if b_dist < dist {
                 ball = b.translation;
                 dist = b_dist;
+
+                let wall_y = config.court_half().y - b_size.0 / 2.;
+                let face_x = t.translation.x - t.translation.x.signum() * size.0.x / 2.;
+                // a ball heading away has a path of just its own position
+                path_end = prediction::predict_path(ball.truncate(), b_v.0, wall_y, face_x)
+                    .last().map(|p| p.y).unwrap_or(ball.y);
             }
         }
 
-        let delta = ball.y - t.translation.y;
+        let target = ball.y + (path_end - ball.y) * foresight;
+        let delta = target - t.translation.y;
         let sign = delta.signum();
-        v.0.y = f32::min(delta.abs(), BOT_PADDLE_SPEED) * sign;
+        v.0.y = f32::min(delta.abs(), config.paddle_speed_of(false)) * sign;
     }
 }
 
@@ -165,157 +979,312 @@ fn add_text(
     pos: Vec2,
     font: &Res<UiFont>,
     who: Who,
+    points: i32,
     component: impl bevy::ecs::component::Component
 ) {
     commands.spawn_bundle(Text2dBundle {
         text: Text::with_section(
-            "0", TextStyle {
+            points.to_string(), TextStyle {
                 font: font.0.clone(),
                 font_size: 100.0,
-                color: bevy::render::color::Color::WHITE,
+                color: Color::WHITE,
             }, Default::default()),
-        transform: Transform::from_xyz(pos.x, pos.y, 0.),
+        transform: Transform::from_xyz(pos.x, pos.y, SCORE_Z),
         ..Default::default()
     })
-        .insert(Score(who, 0))
+        .insert(Score(who, points))
+        .insert(MatchEntity)
         .insert(component);
 }
 
-fn player_input(
+pub fn player_input(
     input : Res<Input<KeyCode>>,
-    mut velocity: Query<&mut Velocity, With<Player>>
+    pad : Res<gamepad::ActivePad>,
+    axes : Res<Axis<GamepadAxis>>,
+    buttons : Res<Input<GamepadButton>>,
+    touches : Res<Touches>,
+    windows : Res<Windows>,
+    mut velocity: Query<(&mut Velocity, &Transform), With<Player>>,
+    mut timings : ResMut<profiler::SystemTimings>,
+    sets : Res<sets::Sets>,
+    config : Res<GameConfig>,
 ) {
-    const SPEED:f32 = PADDLE_SPEED;
-
-    for mut t in velocity.iter_mut() {
-        if input.pressed(KeyCode::S) {
-            t.0.y = -SPEED;
-        } else if input.pressed(KeyCode::W) {
-            t.0.y = SPEED;
-        }
-        else {
-            t.0.y = 0.;
-        }
+    let _timed = timings.time("player_input");
+    let window = windows.get_primary();
+    let devices = controls::Devices {
+        keys: &input,
+        pad: &pad,
+        axes: &axes,
+        buttons: &buttons,
+        cursor: window.and_then(|window| sandbox::cursor_world(window, &config)),
+        touch: window.and_then(|window| touch::held_touch(&touches, window, &config)),
+        mirrored: config.mirror_controls && sets.swapped(),
+    };
+    // stamina is applied in the physics tick, see stamina::apply_stamina
+    for (mut v, t) in velocity.iter_mut() {
+        let speed = config.paddle_speed_of(true);
+        v.0.y = config.input.player.vertical(&devices, t.translation.y, speed) as f32 * speed;
     }
 }
 
-fn paddle_boundaries(
-    mut transform: Query<&mut Transform, With<Paddle>>,
-    windows : Res<Windows>
+pub fn paddle_boundaries(
+    mut transform: Query<(&mut Transform, &PaddleSize), With<Paddle>>,
+    config : Res<GameConfig>,
 ) {
-    let window = windows.get_primary().unwrap();
-    let height = window.height() / 2.;
-    for mut t in transform.iter_mut() {
-        if t.translation.y + PADDLE_HEIGHT / 2.0 > height {
-            t.translation.y = height - PADDLE_HEIGHT / 2.0;
+    let height = config.court_half().y;
+    for (mut t, size) in transform.iter_mut() {
+        let paddle_half = size.0.y / 2.;
+        if t.translation.y + paddle_half > height {
+            t.translation.y = height - paddle_half;
         }
-        else if t.translation.y - PADDLE_HEIGHT / 2. < -height {
-            t.translation.y = -height + PADDLE_HEIGHT / 2.;
+        else if t.translation.y - paddle_half < -height {
+            t.translation.y = -height + paddle_half;
         }
     }
 }
 
-fn ball_bounce(
-    mut transform: Query<(&mut Velocity, &Transform, Entity), With<Ball>>, 
-    paddles : Query<&Transform, With<Paddle>>,
-    windows : Res<Windows>,
-    mut bounce_event : EventWriter<ExitScreenEvent>,
+pub fn ball_bounce(
+    mut commands : Commands,
+    mut transform: Query<(&mut Velocity, &mut Transform, &mut BallKind, &BallSize, &mut LastHit, &mut Squash, Entity), With<Ball>>, 
+    mut paddles : Query<(Entity, &Transform, &PaddleShape, &PaddleSize, &mut Squash, Option<&Player>, Option<&mut PowerShot>), (With<Paddle>, Without<Ball>)>,
+    mut exits : ResMut<exits::ExitQueue>,
+    mut paddle_hits : EventWriter<PaddleHitEvent>,
+    mut wall_bounces : EventWriter<WallBounceEvent>,
+    mut log : ResMut<EventLog>,
+    mut ball_count : ResMut<BallCount>,
+    mut combo : ResMut<combo::Combo>,
+    sets : Res<sets::Sets>,
+    ball_sprite : Res<BallSprite>,
+    mut timings : ResMut<profiler::SystemTimings>,
+    config : Res<GameConfig>,
+    time : Res<Time>,
 ) {
-    let window = windows.get_primary().unwrap();
-    let height = window.height() / 2.;
-    let width = window.width() / 2.;
+    let _timed = timings.time("ball_bounce");
+    let court = config.court_half();
+    let height = court.y;
+    let width = court.x;
+    let goal_half = config.goal_half();
 
-    for (mut v, t, e) in transform.iter_mut() {
-        if t.translation.y + BALL_SIZE / 2. > height 
-            || t.translation.y - BALL_SIZE / 2. < -height {
+    for (mut v, mut t, mut kind, size, mut last_hit, mut ball_squash, e) in transform.iter_mut() {
+        let ball_half = size.0 / 2.;
+
+        if t.translation.y + ball_half > height 
+            || t.translation.y - ball_half < -height {
             v.0.y *= -1.;
+            wall_bounces.send(WallBounceEvent { ball: e, position: t.translation.truncate(), velocity: v.0 });
         }
 
-        if t.translation.x + BALL_SIZE / 2. > width
-            || t.translation.x - BALL_SIZE / 2. < -width {
-            bounce_event.send(ExitScreenEvent(e, if t.translation.x < 0. { Who::PLAYER } else { Who::OPPONENT }));
+        if t.translation.x + ball_half > width
+            || t.translation.x - ball_half < -width {
+            if t.translation.y.abs() <= goal_half {
+                exits.push(e, sets.goal_at(t.translation.x));
+            } else if v.0.x * t.translation.x > 0. {
+                // outside the goal the side edge is a wall
+                v.0.x *= -1.;
+                wall_bounces.send(WallBounceEvent { ball: e, position: t.translation.truncate(), velocity: v.0 });
+            }
         }
 
-        for pt in paddles.iter() {
-            if t.translation.x - BALL_SIZE / 2. < pt.translation.x + PADDLE_WIDTH / 2. 
-                && t.translation.x + BALL_SIZE / 2. > pt.translation.x - PADDLE_WIDTH / 2.
-                && t.translation.y - BALL_SIZE / 2. < pt.translation.y + PADDLE_HEIGHT / 2.
-                && t.translation.y + BALL_SIZE / 2. > pt.translation.y - PADDLE_HEIGHT / 2. {
-                    //v.0.x *= -1.;
-                    let bounce_vector = t.translation - pt.translation;
-                    v.0 = (bounce_vector.normalize() * BALL_SPEED).truncate();
+        for (paddle, pt, shape, size, mut paddle_squash, player, shot) in paddles.iter_mut() {
+            let paddle_half = size.0 / 2.;
+            if collision::overlaps(t.translation.truncate(), Vec2::splat(ball_half), pt.translation.truncate(), paddle_half) {
+                    // paddles face the middle of the court
+                    let facing = -pt.translation.x.signum();
+                    let from_center = collision::resolve_paddle_hit((t.translation - pt.translation).truncate(), ball_half, paddle_half, facing);
+                    let direction = match collision::bounce_direction(*shape, v.0, from_center, paddle_half.y, facing) {
+                        Some(direction) => direction,
+                        None => continue,
+                    };
+                    t.translation.x = pt.translation.x + from_center.x;
+                    v.0 = direction * kind.speed(&config);
+                    if let Some(mut shot) = shot {
+                        if let Some(boost) = shot.boost() {
+                            v.0 *= boost;
+                            shot.fire();
+                        }
+                    }
+                    v.0 = config.limit_ball_velocity(v.0);
+                    if *kind == BallKind::Splitter {
+                        // the new half starts clear of the paddle so the bounce doesn't redirect it next tick
+                        *kind = BallKind::Normal;
+                        let position = Vec2::new(pt.translation.x + facing * (paddle_half.x + ball_half * 2.), t.translation.y);
+                        let (sin, cos) = SPLIT_ANGLE.sin_cos();
+                        let velocity = Vec2::new(v.0.x * cos - v.0.y * sin, v.0.x * sin + v.0.y * cos);
+                        spawn_ball_of_kind(&mut commands, &ball_sprite, &config, BallKind::Normal, position, velocity);
+                        ball_count.0 += 1;
+                    }
+                    ball_squash.hit(squash::BALL_SQUASH);
+                    paddle_squash.hit(squash::PADDLE_SQUASH);
+                    let hitter = if player.is_some() { Who::PLAYER } else { Who::OPPONENT };
+                    // a ball overlapping the paddle for a few ticks is still the one return
+                    if last_hit.0 != Some(hitter) {
+                        combo.returned(hitter, &config);
+                        log.push(time.seconds_since_startup(), GameEvent::PaddleHit(hitter));
+                    }
+                    last_hit.0 = Some(hitter);
+                    paddle_hits.send(PaddleHitEvent { ball: e, paddle, hitter, position: t.translation.truncate(), velocity: v.0 });
                 }
         }
     }
 }
 
-fn spawn_paddles(mut commands: Commands, 
+pub fn spawn_paddles(mut commands: Commands, 
         mat : Res<BallSprite>,
-        windows : Res<Windows>
+        config : Res<GameConfig>,
 ) {
-    let window = windows.get_primary().unwrap();
-    let window_width_half: f32 = window.width() / 2.0;
-    
-    let mut clonedSprite = mat.0.clone();
-    clonedSprite.sprite.custom_size = Some(Vec2::new(PADDLE_WIDTH, PADDLE_HEIGHT));
-    clonedSprite.transform = Transform::from_xyz(-window_width_half + PADDLE_OFFSET, 0., 0.0);
-
     // spawn player
-    commands.spawn()
-        .insert_bundle(clonedSprite)
-        .insert(Velocity(Default::default()))
+    commands.spawn_bundle(PaddleBundle::new(&mat, &config, Who::PLAYER, config.paddle_size(true)).with_shape(config.paddle_shape(true)))
         .insert(Player)
-        .insert(Paddle);
+        .insert(PowerShot::default())
+        .insert(Stamina::default());
     
-    let mut oponnentSprite = mat.0.clone();
-    oponnentSprite.sprite.custom_size = Some(Vec2::new(PADDLE_WIDTH, PADDLE_HEIGHT));
-    oponnentSprite.transform = Transform::from_xyz(window_width_half - PADDLE_OFFSET, 0., 0.0);
-
     // spawn bot
-    commands.spawn()
-        .insert_bundle(oponnentSprite)
-        .insert(Velocity(Default::default()))
-        .insert(Bot)
-        .insert(Paddle);
+    commands.spawn_bundle(PaddleBundle::new(&mat, &config, Who::OPPONENT, config.paddle_size(false)).with_shape(config.paddle_shape(false)))
+        .insert(Bot);
 }
 
-fn spawn_ball(
+/// Serves a new set of balls once the last one is gone. Runs on the fixed tick so the serve lands on the same tick
+/// in a replay, however many ticks each frame runs.
+pub fn spawn_ball(
     mut commands: Commands, 
     mut ball_count : ResMut<BallCount>,
-    mut timer : ResMut<LaunchTimer>,
+    mut rng : ResMut<GameRng>,
     ball_sprite : Res<BallSprite>,
+    config : Res<GameConfig>,
+) {
+    if ball_count.0 != 0 {
+        return;
+    }
+
+    for _i in 0..config.ball_cap() {
+        let kind = config.ball_mix.roll(&mut rng.0);
+        let ball = spawn_ball_of_kind(&mut commands, &ball_sprite, &config, kind, Vec2::ZERO, Vec2::ZERO);
+        commands.entity(ball).insert(ServeTimer::new(&config));
+    }
+
+    ball_count.0 = config.ball_cap() as i32;
+}
+
+fn spawn_ball_of_kind(
+    commands: &mut Commands,
+    ball_sprite: &BallSprite,
+    config: &GameConfig,
+    kind: BallKind,
+    position: Vec2,
+    velocity: Vec2,
+) -> Entity {
+    commands.spawn_bundle(BallBundle::new(ball_sprite, position, velocity, kind.size(config)).with_kind(kind)).id()
+}
+
+/// Keeps ball sprites the size their `BallSize` says.
+pub fn size_ball_sprites(
+    mut balls : Query<(&BallSize, &mut sprite::Sprite), Changed<BallSize>>,
 ) {
-    for _i in 0..BALLS_AMOUNT {
-        commands
-            .spawn()
-            .insert_bundle(ball_sprite.0.clone())
-            .insert(Velocity(Default::default()))
-            .insert(Ball);
+    for (size, mut sprite) in balls.iter_mut() {
+        sprite.custom_size = Some(Vec2::splat(size.0));
     }
+}
 
-    timer.0.reset();
-    ball_count.0 = BALLS_AMOUNT as i32;
+/// Keeps paddle sprites the size their `PaddleSize` says.
+pub fn size_paddle_sprites(
+    mut paddles : Query<(&PaddleSize, &mut sprite::Sprite), Changed<PaddleSize>>,
+) {
+    for (size, mut sprite) in paddles.iter_mut() {
+        sprite.custom_size = Some(size.0);
+    }
 }
 
-fn update_velocity(
+pub fn update_velocity(
     mut velocity : Query<(&Velocity, &mut Transform)>, 
+    mut timings : ResMut<profiler::SystemTimings>,
 ) {
+    let _timed = timings.time("update_velocity");
     for (v, mut t) in velocity.iter_mut() {
         t.translation += v.0.extend(0.);
     }
 }
 
-fn update_score(
-    mut exit_screen_event : EventReader<ExitScreenEvent>,
+/// Zooms the camera so the whole court fits the window, whatever its size. Moving to a monitor with another DPI
+/// scale changes the window's logical size without necessarily being reported as a resize, so scale factor
+/// changes refit it too.
+fn fit_camera_to_court(
+    mut resized : EventReader<WindowResized>,
+    mut rescaled : EventReader<WindowScaleFactorChanged>,
+    mut cameras : Query<&mut OrthographicProjection, Without<scoreboard::ScoreboardCamera>>,
+    windows : Res<Windows>,
+    config : Res<GameConfig>,
+) {
+    // both readers are drained every time, so events left unread don't trigger another pass next frame
+    let resized = resized.iter().count() > 0;
+    let rescaled = rescaled.iter().count() > 0;
+    if !resized && !rescaled && !config.is_changed() {
+        return;
+    }
+
+    let window = windows.get_primary().unwrap();
+    let scale = config.view_scale(window);
+    for mut projection in cameras.iter_mut() {
+        projection.scale = scale;
+    }
+}
+
+/// Re-lays out all text after a DPI scale change, so glyphs are rasterized for the new scale instead of being
+/// stretched from the old one.
+fn refresh_text_on_rescale(
+    mut rescaled : EventReader<WindowScaleFactorChanged>,
+    mut texts : Query<&mut Text>,
+) {
+    if rescaled.iter().count() == 0 {
+        return;
+    }
+    for mut text in texts.iter_mut() {
+        text.set_changed();
+    }
+}
+
+/// Hides balls outside the view so they are skipped by sprite extraction.
+fn cull_off_screen_balls(
+    mut balls : Query<(&Transform, &BallSize, &mut Visibility), (With<Ball>, Without<invisible::InvisibleBall>)>,
+    windows : Res<Windows>,
+    config : Res<GameConfig>,
+) {
+    let window = windows.get_primary().unwrap();
+    let view = Vec2::new(window.width(), window.height()) * config.view_scale(window);
+
+    for (t, size, mut visibility) in balls.iter_mut() {
+        let half = view / 2. + Vec2::splat(size.0 / 2.);
+        let on_screen = t.translation.x.abs() <= half.x && t.translation.y.abs() <= half.y;
+        // only write on change so unchanged balls don't get flagged for re-extraction
+        if visibility.is_visible != on_screen {
+            visibility.is_visible = on_screen;
+        }
+    }
+}
+
+pub fn update_score(
+    mut commands : Commands,
+    mut exits : ResMut<exits::ExitQueue>,
+    mut bonus_event : EventReader<BonusEvent>,
     mut score_event : EventWriter<ScoreEvent>,
     mut scores : Query<(&mut Text, &mut Score)>,
+    balls : Query<(&Transform, &Velocity), With<Ball>>,
+    mut log : ResMut<EventLog>,
+    mut transition : ResMut<Transition>,
+    mut sets : ResMut<sets::Sets>,
+    state : Res<State<GameState>>,
+    config : Res<GameConfig>,
+    time : Res<Time>,
 ) {
 
     fn update_text(text: &mut Text, score : i16) {
         text.sections[0].value = score.to_string();
     }
 
-    for e in exit_screen_event.iter() {
+    fn counter(scores: &mut Query<(&mut Text, &mut Score)>, side: Who) -> i32 {
+        scores.iter_mut().find(|(_, s)| s.0 == side).map(|(_, s)| s.1).unwrap_or_default()
+    }
+
+    for e in exits.take(exits::ExitReader::Score) {
 
         let result:Who = match e.1 {
             Who::PLAYER => Who::OPPONENT,
@@ -329,24 +1298,70 @@ fn update_score(
             }
         }
 
-        score_event.send(ScoreEvent(result));
+        // a replay stops at the end of the match rather than leaving for the game over screen
+        let (total, other) = (counter(&mut scores, e.1), counter(&mut scores, result));
+        if sets::set_won(total, other, &config) && sets.finish_set(result, &config)
+            && *state.current() == GameState::Playing && !transition.is_running() {
+            commands.insert_resource(Winner(result));
+            transition.fade_to(GameState::GameOver);
+        }
+
+        log.push(time.seconds_since_startup(), GameEvent::Score(result));
+        let (position, velocity) = balls.get(e.0).map(|(t, v)| (t.translation.truncate(), v.0)).unwrap_or_default();
+        score_event.send(ScoreEvent { scorer: result, ball: e.0, position, velocity });
+    }
+
+    for BonusEvent(scorer, points) in bonus_event.iter() {
+        // counters are keyed by the side balls go out on, the opposite of who they score for
+        for (mut t, mut s) in scores.iter_mut() {
+            if s.0 != *scorer {
+                s.1 = s.1 + points;
+                update_text(&mut t, s.1.try_into().unwrap_or_default());
+            }
+        }
+
+        let conceded = if *scorer == Who::PLAYER { Who::OPPONENT } else { Who::PLAYER };
+        let (total, other) = (counter(&mut scores, conceded), counter(&mut scores, *scorer));
+        if sets::set_won(total, other, &config) && sets.finish_set(*scorer, &config)
+            && *state.current() == GameState::Playing && !transition.is_running() {
+            commands.insert_resource(Winner(*scorer));
+            transition.fade_to(GameState::GameOver);
+        }
     }
 }
 
-fn remove_off_screen_balls(
-    mut exit_screen_event : EventReader<ExitScreenEvent>,
+pub fn remove_off_screen_balls(
+    mut exits : ResMut<exits::ExitQueue>,
     mut commands : Commands,
     mut ball_count : ResMut<BallCount>,
 ) {
-    for e in exit_screen_event.iter() {
+    for e in exits.take(exits::ExitReader::Despawn) {
         commands.entity(e.0).despawn();
         ball_count.0 -= 1;
     }
 }
 
-fn should_spawn_balls(
-    ball_count : Res<BallCount>
-) -> ShouldRun {
-    if ball_count.0 == 0 { ShouldRun::Yes }
-    else { ShouldRun::No }
-}
\ No newline at end of file
+
+fn advance_tick(
+    mut tick : ResMut<MatchTick>,
+) {
+    tick.0 += 1;
+}
+
+fn teardown_match(
+    mut commands : Commands,
+    entities : Query<Entity, With<MatchEntity>>,
+    mut ball_count : ResMut<BallCount>,
+    mut exits : ResMut<exits::ExitQueue>,
+    mut score_events : ResMut<Events<ScoreEvent>>,
+    mut bonus_events : ResMut<Events<BonusEvent>>,
+) {
+    for e in entities.iter() {
+        commands.entity(e).despawn();
+    }
+    ball_count.0 = 0;
+    // events still in flight refer to the old match and must not score in the next one
+    exits.clear();
+    score_events.clear();
+    bonus_events.clear();
+}
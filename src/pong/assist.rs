@@ -0,0 +1,74 @@
+use bevy::{ecs::prelude::*, math::{Quat, Vec2, Vec3}, render::{color::Color, view::Visibility}, sprite, transform::prelude::*};
+
+use super::{Ball, BallSize, BallSprite, MatchEntity, PaddleSize, Player, Velocity, config::GameConfig, prediction::{self, MAX_BOUNCES}};
+
+const LINE_WIDTH: f32 = 2.;
+const LINE_ALPHA: f32 = 0.25;
+const LINE_Z: f32 = -0.25;
+
+/// One straight piece of the predicted path, `0` being the piece starting at the ball.
+#[derive(Component)]
+pub struct AssistSegment(usize);
+
+pub fn spawn_assist_line(
+    mut commands: Commands,
+    sprite: Res<BallSprite>,
+) {
+    for index in 0..=MAX_BOUNCES {
+        // placed and sized by update_assist_line
+        let mut segment = sprite.tinted(Color::rgba(1., 1., 1., LINE_ALPHA), Vec2::ZERO, Vec3::ZERO);
+        segment.visibility = Visibility { is_visible: false };
+        commands.spawn_bundle(segment)
+            .insert(AssistSegment(index))
+            .insert(MatchEntity);
+    }
+}
+
+/// Draws where the ball closest to the player paddle, and heading for it, will reach the paddle.
+pub fn update_assist_line(
+    players : Query<(&Transform, &PaddleSize), With<Player>>,
+    balls : Query<(&Transform, &Velocity, &BallSize), With<Ball>>,
+    mut segments : Query<(&AssistSegment, &mut sprite::Sprite, &mut Transform, &mut Visibility), (Without<Ball>, Without<Player>)>,
+    config : Res<GameConfig>,
+) {
+    let mut points = Vec::new();
+    if config.trajectory_assist {
+        if let Some((paddle, size)) = players.iter().next() {
+            let paddle = paddle.translation.truncate();
+            let facing = -paddle.x.signum();
+            let nearest = balls.iter()
+                .filter(|(_, v, _)| v.0.x * facing < 0.)
+                .min_by(|(a, _, _), (b, _, _)| {
+                    let a = (a.translation.truncate() - paddle).length_squared();
+                    let b = (b.translation.truncate() - paddle).length_squared();
+                    a.partial_cmp(&b).unwrap_or(std::cmp::Ordering::Equal)
+                });
+            if let Some((t, v, size)) = nearest {
+                let wall_y = config.court_half().y - size.0 / 2.;
+                let face_x = paddle.x + facing * size.0.x / 2.;
+                points = prediction::predict_path(t.translation.truncate(), v.0, wall_y, face_x);
+            }
+        }
+    }
+
+    for (segment, mut sprite, mut t, mut visibility) in segments.iter_mut() {
+        let (start, end) = match (points.get(segment.0), points.get(segment.0 + 1)) {
+            (Some(start), Some(end)) => (*start, *end),
+            _ => {
+                if visibility.is_visible {
+                    visibility.is_visible = false;
+                }
+                continue;
+            }
+        };
+
+        let delta = end - start;
+        let middle = (start + end) / 2.;
+        visibility.is_visible = true;
+        sprite.custom_size = Some(Vec2::new(delta.length(), LINE_WIDTH));
+        t.translation.x = middle.x;
+        t.translation.y = middle.y;
+        t.translation.z = LINE_Z;
+        t.rotation = Quat::from_rotation_z(delta.y.atan2(delta.x));
+    }
+}
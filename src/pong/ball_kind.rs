@@ -0,0 +1,66 @@
+use bevy::ecs::component::Component;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use super::config::GameConfig;
+
+#[derive(Component, Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum BallKind {
+    Normal,
+    /// Big and slow.
+    Heavy,
+    /// Small and quick.
+    Fast,
+    /// Splits into two normal balls the first time it hits a paddle.
+    Splitter,
+}
+
+impl Default for BallKind {
+    fn default() -> Self {
+        BallKind::Normal
+    }
+}
+
+impl BallKind {
+    /// Pixels per physics tick.
+    pub fn speed(&self, config: &GameConfig) -> f32 {
+        let speed = config.per_tick(config.ball_speed);
+        match self {
+            BallKind::Heavy => speed * 0.7,
+            BallKind::Fast => speed * 1.5,
+            BallKind::Normal | BallKind::Splitter => speed,
+        }
+    }
+
+    pub fn size(&self, config: &GameConfig) -> f32 {
+        match self {
+            BallKind::Heavy => config.ball_size * 1.5,
+            BallKind::Fast => config.ball_size * 0.75,
+            BallKind::Normal | BallKind::Splitter => config.ball_size,
+        }
+    }
+}
+
+/// Share of each special kind among served balls; the rest are normal.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BallMix {
+    pub heavy: f32,
+    pub fast: f32,
+    pub splitter: f32,
+}
+
+impl BallMix {
+    pub fn roll(&self, rng: &mut impl Rng) -> BallKind {
+        let roll = rng.gen::<f32>();
+        if roll < self.heavy {
+            BallKind::Heavy
+        } else if roll < self.heavy + self.fast {
+            BallKind::Fast
+        } else if roll < self.heavy + self.fast + self.splitter {
+            BallKind::Splitter
+        } else {
+            BallKind::Normal
+        }
+    }
+}
@@ -0,0 +1,146 @@
+use bevy::{core::prelude::*, ecs::prelude::*, math::Vec2, render::color::Color, sprite, transform::prelude::*};
+use rand::Rng;
+
+use super::{Ball, BallSize, BallSprite, BonusEvent, MatchEntity, Velocity, Who, collision::{self, Collider}, config::GameConfig, profiler::SystemTimings};
+
+const BRICK_COLUMNS: usize = 3;
+const BRICK_ROWS: usize = 8;
+const BRICK_WIDTH: f32 = 20.;
+const BRICK_HEIGHT: f32 = 60.;
+const BRICK_GAP: f32 = 10.;
+/// Bonus points for the paddle that last hit the ball breaking a brick.
+const BRICK_POINTS: i32 = 1;
+/// At the High graphics preset; lower presets spawn fewer.
+const PARTICLES_PER_BRICK: usize = 8;
+const PARTICLE_SIZE: f32 = 4.;
+const PARTICLE_SPEED: f32 = 200.;
+const PARTICLE_LIFETIME: f32 = 0.5;
+
+#[derive(Component)]
+pub struct Brick;
+
+/// The paddle that last hit a ball, who gets the points for the bricks it breaks.
+#[derive(Component, Default)]
+pub struct LastHit(pub Option<Who>);
+
+/// A purely visual spark, in pixels per second since it isn't part of the simulation.
+#[derive(Component)]
+pub struct Particle {
+    velocity: Vec2,
+    life: Timer,
+}
+
+fn brick_color(row: usize) -> Color {
+    let t = row as f32 / (BRICK_ROWS - 1) as f32;
+    Color::rgb(0.9, 0.3 + 0.5 * t, 0.2)
+}
+
+/// Row and position of every brick in a full grid.
+pub fn brick_grid() -> impl Iterator<Item = (usize, Vec2)> {
+    let step = Vec2::new(BRICK_WIDTH, BRICK_HEIGHT) + Vec2::splat(BRICK_GAP);
+    // centre the grid on the middle of the court
+    let origin = -step * Vec2::new(BRICK_COLUMNS as f32 - 1., BRICK_ROWS as f32 - 1.) / 2.;
+    (0..BRICK_ROWS).flat_map(move |row| (0..BRICK_COLUMNS)
+        .map(move |column| (row, origin + step * Vec2::new(column as f32, row as f32))))
+}
+
+pub fn spawn_brick(commands: &mut Commands, sprite: &BallSprite, row: usize, position: Vec2) {
+    let brick = sprite.tinted(brick_color(row), Vec2::new(BRICK_WIDTH, BRICK_HEIGHT), position.extend(0.));
+    commands.spawn_bundle(brick)
+        .insert(Brick)
+        .insert(Collider(Vec2::new(BRICK_WIDTH, BRICK_HEIGHT) / 2.))
+        .insert(MatchEntity);
+}
+
+pub fn spawn_bricks(
+    mut commands: Commands,
+    sprite: Res<BallSprite>,
+    config: Res<GameConfig>,
+) {
+    if !config.breakout {
+        return;
+    }
+    for (row, position) in brick_grid() {
+        spawn_brick(&mut commands, &sprite, row, position);
+    }
+}
+
+/// Bounces balls off bricks and breaks every brick that was hit.
+pub fn break_bricks(
+    mut commands : Commands,
+    mut balls : Query<(&mut Velocity, &Transform, &BallSize, &LastHit), With<Ball>>,
+    bricks : Query<(Entity, &Transform, &Collider), With<Brick>>,
+    mut bonus : EventWriter<BonusEvent>,
+    mut timings : ResMut<SystemTimings>,
+    sprite : Res<BallSprite>,
+    config : Res<GameConfig>,
+) {
+    let _timed = timings.time("break_bricks");
+    let mut alive: Vec<(Entity, Vec2, Vec2)> = bricks.iter()
+        .map(|(e, t, collider)| (e, t.translation.truncate(), collider.0))
+        .collect();
+    if alive.is_empty() {
+        return;
+    }
+
+    // rough bounds of what's left of the grid, so the swarm outside it is skipped cheaply
+    let (min, max) = alive.iter().fold((Vec2::splat(f32::MAX), Vec2::splat(f32::MIN)), |(min, max), (_, p, half)| {
+        (min.min(*p - *half), max.max(*p + *half))
+    });
+
+    for (mut v, t, size, last_hit) in balls.iter_mut() {
+        let position = t.translation.truncate();
+        let ball_half = Vec2::splat(size.0 / 2.);
+        if (position + ball_half).cmplt(min).any() || (position - ball_half).cmpgt(max).any() {
+            continue;
+        }
+
+        let hit = alive.iter().position(|(_, p, half)| collision::overlaps(position, ball_half, *p, *half));
+        let (e, brick, half) = match hit {
+            Some(i) => alive.swap_remove(i),
+            None => continue,
+        };
+
+        let normal = collision::contact_normal(position, ball_half, brick, half);
+        // only turn around if still heading into the brick
+        if v.0.dot(normal) < 0. {
+            v.0 = collision::reflect(v.0, normal);
+        }
+
+        commands.entity(e).despawn();
+        spawn_particles(&mut commands, &sprite, &config, brick);
+        if let Some(scorer) = last_hit.0 {
+            bonus.send(BonusEvent(scorer, BRICK_POINTS));
+        }
+    }
+}
+
+fn spawn_particles(commands: &mut Commands, sprite: &BallSprite, config: &GameConfig, position: Vec2) {
+    let mut rng = rand::thread_rng();
+    let count = (PARTICLES_PER_BRICK as f32 * config.graphics_quality.particles()).round() as usize;
+    for _ in 0..count {
+        let angle = rng.gen_range(0.0..std::f32::consts::TAU);
+        let speed = rng.gen_range(0.5..1.0) * PARTICLE_SPEED;
+        commands.spawn_bundle(sprite.bundle(Vec2::splat(PARTICLE_SIZE), position.extend(1.)))
+            .insert(Particle {
+                velocity: Vec2::new(angle.cos(), angle.sin()) * speed,
+                life: Timer::from_seconds(PARTICLE_LIFETIME, false),
+            })
+            .insert(MatchEntity);
+    }
+}
+
+pub fn update_particles(
+    mut commands : Commands,
+    mut particles : Query<(Entity, &mut Particle, &mut Transform, &mut sprite::Sprite)>,
+    time : Res<Time>,
+) {
+    for (e, mut particle, mut t, mut sprite) in particles.iter_mut() {
+        if particle.life.tick(time.delta()).finished() {
+            commands.entity(e).despawn();
+            continue;
+        }
+        t.translation += (particle.velocity * time.delta_seconds()).extend(0.);
+        sprite.color.set_a(1. - particle.life.percent());
+    }
+}
@@ -0,0 +1,138 @@
+use bevy::{ecs::component::Component, math::Vec2};
+use serde::{Deserialize, Serialize};
+
+/// How far the convex face tilts the normal at its ends, as `tan` of the tilt angle.
+const CONVEX_CURVE: f32 = 0.6;
+/// Tilt of each half of a V-shaped face, as `tan` of the tilt angle.
+const V_SLOPE: f32 = 0.4;
+/// Shortest vector still trusted to have a direction.
+const MIN_LENGTH: f32 = 1e-6;
+
+#[derive(Component, Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum PaddleShape {
+    /// The ball leaves along the line from the paddle centre to the ball.
+    Classic,
+    /// A flat face: a mirror reflection.
+    Flat,
+    /// A rounded face whose normal tilts further the closer to the end the ball hits.
+    Convex,
+    /// Two flat halves angled away from the centre.
+    VShape,
+}
+
+impl PaddleShape {
+    const ALL: [PaddleShape; 4] = [PaddleShape::Classic, PaddleShape::Flat, PaddleShape::Convex, PaddleShape::VShape];
+
+    /// The shape `delta` steps away, clamped to the first and last shape.
+    pub fn step(&self, delta: i32) -> Self {
+        let index = Self::ALL.iter().position(|s| s == self).unwrap_or_default() as i32;
+        Self::ALL[(index + delta).clamp(0, Self::ALL.len() as i32 - 1) as usize]
+    }
+}
+
+/// Axis-aligned box collider given by its half extents, centred on the entity's translation.
+#[derive(Component)]
+pub struct Collider(pub Vec2);
+
+pub fn overlaps(a: Vec2, a_half: Vec2, b: Vec2, b_half: Vec2) -> bool {
+    let gap = (a - b).abs() - (a_half + b_half);
+    gap.x < 0. && gap.y < 0.
+}
+
+/// Normal of the side of box `b` that box `a` came in through, taken as the axis `a` overlaps it least on.
+pub fn contact_normal(a: Vec2, a_half: Vec2, b: Vec2, b_half: Vec2) -> Vec2 {
+    let delta = a - b;
+    let depth = (a_half + b_half) - delta.abs();
+    if depth.x < depth.y {
+        Vec2::new(delta.x.signum(), 0.)
+    } else {
+        Vec2::new(0., delta.y.signum())
+    }
+}
+
+/// Surface normal of the paddle face at `offset` (-1 bottom edge to 1 top edge).
+/// `facing` is the x direction the face points in.
+pub fn paddle_normal(shape: PaddleShape, offset: f32, facing: f32) -> Vec2 {
+    let offset = offset.clamp(-1., 1.);
+    let tilt = match shape {
+        PaddleShape::Classic | PaddleShape::Flat => 0.,
+        PaddleShape::Convex => offset * CONVEX_CURVE,
+        PaddleShape::VShape => offset.signum() * V_SLOPE,
+    };
+    Vec2::new(facing, tilt).normalize()
+}
+
+/// `v` scaled to unit length, or `fallback` if `v` is too short (or too long) to normalize without
+/// producing NaNs.
+pub fn normalize_or(v: Vec2, fallback: Vec2) -> Vec2 {
+    let length = v.length();
+    if length > MIN_LENGTH && length.is_finite() {
+        v / length
+    } else {
+        fallback
+    }
+}
+
+/// `velocity` slowed to at most `max_speed`, then turned, keeping its speed and the sign of each
+/// component, until it crosses the court at least `min_x` a tick. A ball slower than `min_x` goes
+/// straight across at the speed it has.
+pub fn limit_velocity(velocity: Vec2, min_x: f32, max_speed: f32) -> Vec2 {
+    let mut speed = velocity.length();
+    let mut limited = velocity;
+    // capped first, so slowing the ball down can't take it back under min_x
+    if speed > max_speed {
+        limited *= max_speed / speed;
+        speed = max_speed;
+    }
+    if limited.x.abs() < min_x {
+        let x = min_x.min(speed);
+        limited = Vec2::new(x.copysign(limited.x), (speed * speed - x * x).max(0.).sqrt().copysign(limited.y));
+    }
+    limited
+}
+
+pub fn reflect(velocity: Vec2, normal: Vec2) -> Vec2 {
+    velocity - 2. * velocity.dot(normal) * normal
+}
+
+/// Where a ball hitting a paddle is moved to, relative to the paddle centre: out in front of the
+/// face if it has sunk into the paddle, so it is clear of it as soon as it moves on.
+pub fn resolve_paddle_hit(from_center: Vec2, ball_half: f32, paddle_half: Vec2, facing: f32) -> Vec2 {
+    let clear = paddle_half.x + ball_half;
+    if from_center.x * facing < clear {
+        Vec2::new(clear * facing, from_center.y)
+    } else {
+        from_center
+    }
+}
+
+/// Unit direction a ball leaves the paddle in, or `None` if it shouldn't bounce this tick.
+///
+/// `from_center` is the ball position relative to the paddle centre and `half_height` half the
+/// paddle height.
+pub fn bounce_direction(
+    shape: PaddleShape,
+    velocity: Vec2,
+    from_center: Vec2,
+    half_height: f32,
+    facing: f32,
+) -> Option<Vec2> {
+    // a ball dead on the paddle centre goes straight back out the face
+    let straight_out = Vec2::new(facing, 0.);
+    if shape == PaddleShape::Classic {
+        return Some(normalize_or(from_center, straight_out));
+    }
+
+    // a ball already heading away is still overlapping from last tick's bounce
+    if velocity.x * facing >= 0. {
+        return None;
+    }
+
+    let normal = paddle_normal(shape, from_center.y / half_height, facing);
+    let mut direction = reflect(velocity, normal);
+    // steep normals near the ends can send the ball back into the paddle
+    if direction.x * facing < 0. {
+        direction.x = -direction.x;
+    }
+    Some(normalize_or(direction, straight_out))
+}
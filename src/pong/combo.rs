@@ -0,0 +1,109 @@
+use bevy::{ecs::prelude::*, math::Vec2, render::color::Color, text::prelude::*, transform::prelude::*};
+use serde::{Deserialize, Serialize};
+
+use super::{BonusEvent, MatchEntity, ScoreEvent, UiFont, Who, config::GameConfig};
+
+const COMBO_Z: f32 = 5.;
+const COMBO_MARGIN: f32 = 60.;
+const FONT_SIZE: f32 = 40.;
+/// Returns in a row before the counter shows up.
+const SHOW_FROM: u32 = 2;
+
+/// Returns in a row each side has made since the last point, for `GameConfig::combo_returns`.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Combo {
+    player: u32,
+    bot: u32,
+    /// Sides that completed a combo this tick, waiting for their bonus point.
+    #[serde(skip)]
+    completed: Vec<Who>,
+}
+
+impl Combo {
+    pub fn count(&self, who: Who) -> u32 {
+        match who {
+            Who::PLAYER => self.player,
+            Who::OPPONENT => self.bot,
+        }
+    }
+
+    /// Counts a return by `hitter`.
+    pub fn returned(&mut self, hitter: Who, config: &GameConfig) {
+        let count = match hitter {
+            Who::PLAYER => &mut self.player,
+            Who::OPPONENT => &mut self.bot,
+        };
+        *count += 1;
+        if config.combo_returns > 0 && *count % config.combo_returns == 0 {
+            self.completed.push(hitter);
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct ComboText(Who);
+
+pub fn reset_combo(
+    mut combo : ResMut<Combo>,
+) {
+    *combo = Combo::default();
+}
+
+pub fn spawn_combo_text(
+    mut commands : Commands,
+    font : Res<UiFont>,
+    config : Res<GameConfig>,
+) {
+    let court = config.court_half();
+    for (who, x) in [(Who::PLAYER, -court.x / 2.), (Who::OPPONENT, court.x / 2.)] {
+        let pos = Vec2::new(x, court.y - COMBO_MARGIN);
+        commands.spawn_bundle(Text2dBundle {
+            text: Text::with_section(
+                "", TextStyle {
+                    font: font.0.clone(),
+                    font_size: FONT_SIZE,
+                    color: Color::YELLOW,
+                }, TextAlignment {
+                    vertical: VerticalAlign::Center,
+                    horizontal: HorizontalAlign::Center,
+                }),
+            transform: Transform::from_xyz(pos.x, pos.y, COMBO_Z),
+            ..Default::default()
+        })
+            .insert(ComboText(who))
+            .insert(MatchEntity);
+    }
+}
+
+/// Awards a bonus point per completed combo, and ends every combo when a point is scored.
+pub fn score_combos(
+    mut combo : ResMut<Combo>,
+    mut scores : EventReader<ScoreEvent>,
+    mut bonus : EventWriter<BonusEvent>,
+) {
+    if !combo.completed.is_empty() {
+        for hitter in combo.completed.drain(..) {
+            bonus.send(BonusEvent(hitter, 1));
+        }
+    }
+    if scores.iter().count() > 0 {
+        combo.player = 0;
+        combo.bot = 0;
+    }
+}
+
+pub fn update_combo_text(
+    combo : Res<Combo>,
+    mut texts : Query<(&mut Text, &ComboText)>,
+) {
+    if !combo.is_changed() {
+        return;
+    }
+    for (mut text, who) in texts.iter_mut() {
+        let count = combo.count(who.0);
+        let value = if count >= SHOW_FROM { format!("{} in a row", count) } else { String::new() };
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+    }
+}
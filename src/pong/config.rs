@@ -0,0 +1,479 @@
+use std::{fs, io, str::FromStr, time::{Duration, SystemTime}};
+
+use bevy::{core::prelude::*, ecs::prelude::*, log::{info, warn}, math::Vec2, transform::prelude::*, window::prelude::*};
+use serde::{Deserialize, Serialize};
+
+use super::{Ball, BallCount, BallSize, GameState, Paddle, PaddleSize, Player, ServeTimer, Velocity, Who, ball_kind::{BallKind, BallMix}, collision::{self, PaddleShape}, controls::InputAssignment, courts::CourtTheme, hazards::HazardConfig, logging::LogConfig, menu::MenuAction, obstacles::ObstacleConfig, portals::PortalPairConfig, sets::Sets};
+
+pub const CONFIG_PATH: &str = "config.ron";
+const WATCH_INTERVAL: f32 = 1.0;
+/// Extra scale `GameConfig::large_text` puts on top of `GameConfig::text_scale`.
+const LARGE_TEXT_SCALE: f32 = 1.5;
+
+/// Gameplay tuning values read from `config.ron`. Speeds are in pixels per second.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameConfig {
+    pub paddle_offset: f32,
+    pub paddle_width: f32,
+    pub paddle_height: f32,
+    pub paddle_speed: f32,
+    pub bot_paddle_speed: f32,
+    pub ball_size: f32,
+    pub ball_speed: f32,
+    pub ball_launch_time: f32,
+    /// Slowest a ball may cross the court after a bounce, so it can't end up bouncing between the
+    /// top and bottom forever. 0 for no minimum.
+    pub min_ball_speed_x: f32,
+    /// Fastest a ball may leave a bounce at, power shots included. 0 for no cap.
+    pub max_ball_speed: f32,
+    /// Physics ticks per second. Higher is more precise, lower is cheaper; a reload takes effect from the next match.
+    pub tick_rate: u32,
+    pub balls_amount: i64,
+    pub ball_mix: BallMix,
+    pub score_limit: i32,
+    /// Deuce: a set also needs a two point lead, so it goes on past `score_limit` until someone has one.
+    pub win_by_two: bool,
+    /// Points between the serve changing hands, served towards the receiver; 0 serves in a random direction.
+    pub serve_every: u32,
+    /// Sets of `score_limit` points needed to win the match.
+    pub sets_to_win: u32,
+    /// Swap ends with the bot after each set.
+    pub switch_sides: bool,
+    /// While playing from the right end, swap W/S for the arrow keys and back, so the hands swap too.
+    pub mirror_controls: bool,
+    pub graphics_quality: GraphicsQuality,
+    pub vsync: bool,
+    /// Frames per second to cap rendering at, 0 for uncapped.
+    pub frame_limit: u32,
+    /// Font every text is drawn in, relative to `assets/`.
+    pub font: String,
+    /// Multiplies every font size.
+    pub text_scale: f32,
+    /// Accessibility: draws all text half as big again, on top of `text_scale`.
+    pub large_text: bool,
+    /// Name of the layout from `assets/courts/` the court fields below came from.
+    pub court: String,
+    pub court_theme: CourtTheme,
+    /// Size of the playing field in world units. The camera is scaled to fit it to the window.
+    pub court_width: f32,
+    pub court_height: f32,
+    /// Height of the goal in the middle of each side edge; the rest of the edge is wall.
+    /// Anything at least `court_height` makes the whole edge a goal.
+    pub goal_height: f32,
+    pub player_paddle_shape: PaddleShape,
+    pub bot_paddle_shape: PaddleShape,
+    /// Whether moving the player paddle uses up stamina, slowing it down when empty.
+    pub stamina: bool,
+    /// Breakout hybrid: a grid of bricks mid-court that balls break for bonus points.
+    pub breakout: bool,
+    /// Hides the bot's half of the court beyond the ball furthest into it.
+    pub fog_of_war: bool,
+    /// Mutator: balls are hidden except for a flash every second and on every bounce.
+    pub invisible_balls: bool,
+    /// Darkens the court, with each ball lighting up the area around it.
+    pub night_mode: bool,
+    /// Consecutive returns by one side within a rally that earn it a bonus point, 0 for none.
+    pub combo_returns: u32,
+    /// Gravity wells and repulsors placed on the court.
+    pub hazards: Vec<HazardConfig>,
+    /// Linked portals that teleport balls from one end to the other.
+    pub portals: Vec<PortalPairConfig>,
+    /// Solid boxes on the court, either fixed or patrolling a path.
+    pub obstacles: Vec<ObstacleConfig>,
+    /// Draws the predicted path of the ball heading for the player paddle.
+    pub trajectory_assist: bool,
+    /// Opens a second window showing just the scores and match time, for a second monitor. Read at startup only.
+    pub scoreboard_window: bool,
+    /// Seconds without any input before a match pauses itself, 0 to never pause.
+    pub idle_pause: f32,
+    /// Further idle seconds on the pause screen before quitting to the menu, 0 to stay paused.
+    pub idle_quit: f32,
+    /// Most replays of finished matches kept in `replays/`, the oldest being deleted first. 0 keeps them all.
+    pub max_replays: u32,
+    pub log: LogConfig,
+    pub input: InputAssignment,
+    pub player_handicap: Handicap,
+    pub bot_handicap: Handicap,
+}
+
+/// Evens out a match between players of different skill.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Handicap {
+    /// Multiplies the paddle's width and height.
+    pub paddle_scale: f32,
+    /// Points to start the match with.
+    pub head_start: i32,
+    /// Fraction added to the paddle's speed, e.g. 0.25 for a quarter faster.
+    pub speed_bonus: f32,
+}
+
+impl Default for Handicap {
+    fn default() -> Self {
+        Self {
+            paddle_scale: 1.,
+            head_start: 0,
+            speed_bonus: 0.,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum GraphicsQuality { Low, Medium, High }
+
+impl GraphicsQuality {
+    const ALL: [GraphicsQuality; 3] = [GraphicsQuality::Low, GraphicsQuality::Medium, GraphicsQuality::High];
+
+    /// Most balls allowed on the field at once.
+    pub fn max_balls(&self) -> i64 {
+        match self {
+            GraphicsQuality::Low => 1_000,
+            GraphicsQuality::Medium => 10_000,
+            GraphicsQuality::High => i64::MAX,
+        }
+    }
+
+    /// Multiplier for how many particles effects spawn.
+    pub fn particles(&self) -> f32 {
+        match self {
+            GraphicsQuality::Low => 0.25,
+            GraphicsQuality::Medium => 0.5,
+            GraphicsQuality::High => 1.,
+        }
+    }
+
+    /// The preset `delta` steps away, clamped to Low..High.
+    pub fn step(&self, delta: i32) -> Self {
+        let index = Self::ALL.iter().position(|q| q == self).unwrap_or_default() as i32;
+        Self::ALL[(index + delta).clamp(0, Self::ALL.len() as i32 - 1) as usize]
+    }
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self {
+            paddle_offset: super::PADDLE_OFFSET,
+            paddle_width: super::PADDLE_WIDTH,
+            paddle_height: super::PADDLE_HEIGHT,
+            paddle_speed: super::PADDLE_SPEED,
+            bot_paddle_speed: super::BOT_PADDLE_SPEED,
+            ball_size: super::BALL_SIZE,
+            ball_speed: super::BALL_SPEED,
+            ball_launch_time: super::BALL_LAUNCH_TIME,
+            min_ball_speed_x: 240.,
+            max_ball_speed: 2400.,
+            tick_rate: super::TICK_RATE,
+            balls_amount: super::BALLS_AMOUNT,
+            ball_mix: BallMix::default(),
+            score_limit: super::SCORE_LIMIT,
+            win_by_two: false,
+            serve_every: 0,
+            sets_to_win: 1,
+            switch_sides: true,
+            mirror_controls: false,
+            graphics_quality: GraphicsQuality::High,
+            vsync: true,
+            frame_limit: 0,
+            font: "Consola.ttf".to_string(),
+            text_scale: 1.,
+            large_text: false,
+            court: "Classic".to_string(),
+            court_theme: CourtTheme::default(),
+            court_width: super::COURT_WIDTH,
+            court_height: super::COURT_HEIGHT,
+            goal_height: super::COURT_HEIGHT,
+            player_paddle_shape: PaddleShape::Classic,
+            bot_paddle_shape: PaddleShape::Classic,
+            stamina: false,
+            breakout: false,
+            fog_of_war: false,
+            invisible_balls: false,
+            night_mode: false,
+            combo_returns: 0,
+            hazards: Vec::new(),
+            portals: Vec::new(),
+            obstacles: Vec::new(),
+            trajectory_assist: false,
+            scoreboard_window: false,
+            idle_pause: 60.,
+            idle_quit: 240.,
+            max_replays: 50,
+            log: LogConfig::default(),
+            input: InputAssignment::default(),
+            player_handicap: Handicap::default(),
+            bot_handicap: Handicap::default(),
+        }
+    }
+}
+
+/// The next argument parsed as a value, if there is one and it parses.
+fn parse_next<T: FromStr>(args: &mut impl Iterator<Item = String>) -> Option<T> {
+    args.next()?.parse().ok()
+}
+
+impl GameConfig {
+    /// Applies command line overrides: `--balls <n>`, `--ball-speed <speed>`, `--paddle-speed <speed>`,
+    /// `--bot-speed <speed>`, `--score-limit <points>`, `--breakout`, `--handheld`, `--scoreboard`, and the
+    /// logging arguments.
+    pub fn apply_args(&mut self, mut args: impl Iterator<Item = String>) {
+        while let Some(arg) = args.next() {
+            let applied = match arg.as_str() {
+                "--balls" => parse_next(&mut args).map(|n| self.balls_amount = n).is_some(),
+                "--ball-speed" => parse_next(&mut args).map(|speed| self.ball_speed = speed).is_some(),
+                "--paddle-speed" => parse_next(&mut args).map(|speed| self.paddle_speed = speed).is_some(),
+                "--bot-speed" => parse_next(&mut args).map(|speed| self.bot_paddle_speed = speed).is_some(),
+                "--score-limit" => parse_next(&mut args).map(|points| self.score_limit = points).is_some(),
+                "--breakout" => {
+                    self.breakout = true;
+                    true
+                }
+                "--handheld" => {
+                    super::handheld::apply_preset(self);
+                    true
+                }
+                "--scoreboard" => {
+                    self.scoreboard_window = true;
+                    true
+                }
+                _ => self.log.apply_arg(&arg, &mut args),
+            };
+            if !applied {
+                eprintln!("ignoring argument {}: unknown or missing a valid value", arg);
+            }
+        }
+    }
+
+    /// How many balls to serve, limited by the graphics preset.
+    pub fn ball_cap(&self) -> i64 {
+        self.balls_amount.min(self.graphics_quality.max_balls())
+    }
+
+    /// Half extents of the court, centred on the origin.
+    pub fn court_half(&self) -> Vec2 {
+        Vec2::new(self.court_width, self.court_height) / 2.
+    }
+
+    /// Half the height of the goals, never more than the court.
+    pub fn goal_half(&self) -> f32 {
+        self.goal_height.min(self.court_height) / 2.
+    }
+
+    pub fn paddle_shape(&self, player: bool) -> PaddleShape {
+        if player { self.player_paddle_shape } else { self.bot_paddle_shape }
+    }
+
+    pub fn handicap(&self, player: bool) -> &Handicap {
+        if player { &self.player_handicap } else { &self.bot_handicap }
+    }
+
+    /// Size of the player's or bot's paddle, handicap included.
+    pub fn paddle_size(&self, player: bool) -> Vec2 {
+        Vec2::new(self.paddle_width, self.paddle_height) * self.handicap(player).paddle_scale
+    }
+
+    /// Seconds per physics tick.
+    pub fn timestep(&self) -> f64 {
+        1. / self.tick_rate.max(1) as f64
+    }
+
+    /// Converts a duration in seconds to whole physics ticks, at least one.
+    pub fn ticks(&self, seconds: f32) -> u32 {
+        (seconds as f64 / self.timestep()).round().max(1.) as u32
+    }
+
+    /// Converts a speed in pixels per second to pixels per physics tick.
+    pub fn per_tick(&self, per_second: f32) -> f32 {
+        per_second * self.timestep() as f32
+    }
+
+    /// `velocity` (in pixels per tick) held to `min_ball_speed_x` and `max_ball_speed`.
+    pub fn limit_ball_velocity(&self, velocity: Vec2) -> Vec2 {
+        let max_speed = if self.max_ball_speed > 0. { self.per_tick(self.max_ball_speed) } else { f32::INFINITY };
+        collision::limit_velocity(velocity, self.per_tick(self.min_ball_speed_x), max_speed)
+    }
+
+    /// Top speed of the player's or bot's paddle in pixels per tick, handicap included.
+    pub fn paddle_speed_of(&self, player: bool) -> f32 {
+        let base = if player { self.paddle_speed } else { self.bot_paddle_speed };
+        self.per_tick(base * (1. + self.handicap(player).speed_bonus))
+    }
+
+    /// World units per window pixel needed for the whole court to be visible.
+    pub fn view_scale(&self, window: &Window) -> f32 {
+        if window.width() <= 0. || window.height() <= 0. {
+            return 1.;
+        }
+        f32::max(self.court_width / window.width(), self.court_height / window.height())
+    }
+
+    /// What every text's font size is multiplied by.
+    pub fn font_scale(&self) -> f32 {
+        let large = if self.large_text { LARGE_TEXT_SCALE } else { 1. };
+        self.text_scale.max(0.1) * large
+    }
+}
+
+/// Reads the config file, falling back to defaults when it doesn't exist.
+pub fn load() -> Result<GameConfig, String> {
+    match fs::read_to_string(CONFIG_PATH) {
+        Ok(data) => ron::from_str(&data).map_err(|e| e.to_string()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(GameConfig::default()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Writes `config` out as the config file, without the comments `config.ron` ships with.
+pub fn save(config: &GameConfig) -> Result<(), String> {
+    let data = ron::ser::to_string_pretty(config, Default::default()).map_err(|e| e.to_string())?;
+    fs::write(CONFIG_PATH, data).map_err(|e| e.to_string())
+}
+
+fn modified_time() -> Option<SystemTime> {
+    fs::metadata(CONFIG_PATH).and_then(|m| m.modified()).ok()
+}
+
+pub struct ConfigWatcher {
+    timer: Timer,
+    modified: Option<SystemTime>,
+    /// A tick rate reloaded from the file, held back until the next match starts. Every per-tick
+    /// speed is worked out from `GameConfig::tick_rate`, so it has to match the rate the match runs at.
+    pending_tick_rate: Option<u32>,
+}
+
+impl ConfigWatcher {
+    pub fn new() -> Self {
+        Self {
+            timer: Timer::from_seconds(WATCH_INTERVAL, true),
+            modified: modified_time(),
+            pending_tick_rate: None,
+        }
+    }
+
+    /// The reloaded tick rate to switch to, if it differs from the one running.
+    pub fn take_tick_rate(&mut self) -> Option<u32> {
+        self.pending_tick_rate.take()
+    }
+}
+
+/// What the running game changed on top of the config file: the command line arguments and the
+/// settings picked from the menus. Reapplied after every reload, so editing the file doesn't undo them.
+#[derive(Default)]
+pub struct ConfigOverrides {
+    pub args: Vec<String>,
+    /// Settings items used so far, each once.
+    pub settings: Vec<MenuAction>,
+}
+
+impl ConfigOverrides {
+    pub fn changed_setting(&mut self, action: MenuAction) {
+        if !self.settings.contains(&action) {
+            self.settings.push(action);
+        }
+    }
+}
+
+/// Reloads `config.ron` when it changes. Only installed when the config was read from the file,
+/// so a config handed to `PongApp` isn't replaced by the file's.
+pub fn watch_config(
+    watcher : Option<ResMut<ConfigWatcher>>,
+    mut config : ResMut<GameConfig>,
+    overrides : Res<ConfigOverrides>,
+    time : Res<Time>,
+) {
+    let mut watcher = match watcher {
+        Some(watcher) => watcher,
+        None => return,
+    };
+    if !watcher.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let modified = modified_time();
+    if modified == watcher.modified {
+        return;
+    }
+    watcher.modified = modified;
+
+    match load() {
+        Ok(mut new_config) => {
+            new_config.apply_args(overrides.args.iter().cloned());
+            for action in overrides.settings.iter() {
+                action.copy_setting(&config, &mut new_config);
+            }
+            // picked up by sim_clock::apply_tick_rate
+            watcher.pending_tick_rate = Some(new_config.tick_rate).filter(|rate| *rate != config.tick_rate);
+            new_config.tick_rate = config.tick_rate;
+            *config = new_config;
+            info!("reloaded {}", CONFIG_PATH);
+        }
+        Err(e) => warn!("could not reload {}: {}", CONFIG_PATH, e),
+    }
+}
+
+/// Pushes config changes onto entities and resources that were created from the old values. Balls are
+/// only resized or sped up when the ball size or per-tick speed changes, and nothing in the match
+/// is touched during a replay.
+pub fn apply_config(
+    mut commands : Commands,
+    config : Res<GameConfig>,
+    mut windows : ResMut<Windows>,
+    mut ball_count : ResMut<BallCount>,
+    sets : Res<Sets>,
+    mut paddles : Query<(&mut PaddleSize, &mut Transform, &mut PaddleShape, Option<&Player>), (With<Paddle>, Without<Ball>)>,
+    mut balls : Query<(Entity, &mut BallSize, &mut Velocity, &BallKind), With<Ball>>,
+    mut serves : Query<&mut ServeTimer>,
+    mut applied : Local<Option<(f32, f32)>>,
+    state : Res<State<GameState>>,
+) {
+    if !config.is_changed() {
+        return;
+    }
+
+    let window = windows.get_primary_mut().unwrap();
+    if window.vsync() != config.vsync {
+        window.set_vsync(config.vsync);
+    }
+    // the per-tick speed and size the balls were last set up for
+    let (speed, size) = (config.per_tick(config.ball_speed), config.ball_size);
+    let (old_speed, old_size) = applied.replace((speed, size)).unwrap_or((speed, size));
+    // a replay re-simulates the match with the config it was recorded with
+    if *state.current() == GameState::Replay {
+        return;
+    }
+    let court_width_half = config.court_half().x;
+
+    for (mut size, mut t, mut shape, player) in paddles.iter_mut() {
+        size.0 = config.paddle_size(player.is_some());
+        let who = if player.is_some() { Who::PLAYER } else { Who::OPPONENT };
+        t.translation.x = sets.side_of(who) * (court_width_half - config.paddle_offset);
+        *shape = config.paddle_shape(player.is_some());
+    }
+
+    let cap = config.ball_cap();
+    let mut kept = 0;
+    for (e, mut ball_size, mut v, kind) in balls.iter_mut() {
+        // a lower quality preset takes effect immediately by dropping the surplus balls
+        if kept >= cap {
+            commands.entity(e).despawn();
+            continue;
+        }
+        kept += 1;
+
+        if size != old_size {
+            ball_size.0 = kind.size(&config);
+        }
+        // scaled rather than reset, so boosts and speed picked up from the limits are kept
+        if speed != old_speed && old_speed > 0. {
+            v.0 *= speed / old_speed;
+        }
+    }
+
+    if (kept as i32) < ball_count.0 {
+        ball_count.0 = kept as i32;
+    }
+
+    for mut timer in serves.iter_mut() {
+        timer.0.set_duration(Duration::from_secs_f32(config.ball_launch_time));
+    }
+}
@@ -0,0 +1,101 @@
+use bevy::{input::{gamepad::{Gamepad, GamepadAxis, GamepadButton}, prelude::*}, math::Vec2};
+use serde::{Deserialize, Serialize};
+
+use super::{gamepad::{self, ActivePad}, touch};
+
+/// What moves a local player's paddle.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum InputDevice {
+    /// W/S, the active gamepad or the touch zones, whichever is in use.
+    Any,
+    /// W and S.
+    KeyboardLeft,
+    /// The up and down arrows.
+    KeyboardRight,
+    /// The first gamepad connected.
+    Gamepad,
+    /// Gamepad N, numbered as the system reports them from 0, for picking one of several.
+    Pad(usize),
+    /// The paddle chases the cursor.
+    Mouse,
+    /// Holding a finger on the top or bottom half of the screen.
+    Touch,
+}
+
+impl InputDevice {
+    const ALL: [InputDevice; 10] = [
+        InputDevice::Any, InputDevice::KeyboardLeft, InputDevice::KeyboardRight, InputDevice::Gamepad,
+        InputDevice::Pad(0), InputDevice::Pad(1), InputDevice::Pad(2), InputDevice::Pad(3),
+        InputDevice::Mouse, InputDevice::Touch,
+    ];
+
+    pub fn step(self, delta: i32) -> Self {
+        let index = Self::ALL.iter().position(|d| *d == self).unwrap_or_default() as i32;
+        Self::ALL[(index + delta).clamp(0, Self::ALL.len() as i32 - 1) as usize]
+    }
+
+    pub fn uses_gamepad(self) -> bool {
+        matches!(self, InputDevice::Any | InputDevice::Gamepad | InputDevice::Pad(_))
+    }
+
+    pub fn uses_touch(self) -> bool {
+        matches!(self, InputDevice::Any | InputDevice::Touch)
+    }
+
+    /// -1, 0 or 1 for moving a paddle at `paddle_y` down, not at all or up. The mouse stops the paddle once the
+    /// cursor is within a tick's travel of `speed`, so it doesn't jitter. Input is digital so replays record it
+    /// exactly.
+    pub fn vertical(self, devices: &Devices, paddle_y: f32, speed: f32) -> i8 {
+        let (left, right) = ((KeyCode::W, KeyCode::S), (KeyCode::Up, KeyCode::Down));
+        let (left, right) = if devices.mirrored { (right, left) } else { (left, right) };
+        match self {
+            InputDevice::Any => match keys(devices.keys, left.0, left.1) {
+                0 => match devices.pad.vertical(devices.axes, devices.buttons) {
+                    0 => devices.touch.map(touch::controls_direction).unwrap_or(0),
+                    pad => pad,
+                },
+                keys => keys,
+            },
+            InputDevice::KeyboardLeft => keys(devices.keys, left.0, left.1),
+            InputDevice::KeyboardRight => keys(devices.keys, right.0, right.1),
+            InputDevice::Gamepad => devices.pad.vertical(devices.axes, devices.buttons),
+            InputDevice::Pad(index) => gamepad::vertical(Gamepad(index), devices.axes, devices.buttons),
+            InputDevice::Mouse => {
+                let distance = devices.cursor.map(|cursor| cursor.y - paddle_y).unwrap_or(0.);
+                if distance > speed { 1 } else if distance < -speed { -1 } else { 0 }
+            }
+            InputDevice::Touch => devices.touch.map(touch::controls_direction).unwrap_or(0),
+        }
+    }
+}
+
+fn keys(keys: &Input<KeyCode>, up: KeyCode, down: KeyCode) -> i8 {
+    if keys.pressed(down) { -1 } else if keys.pressed(up) { 1 } else { 0 }
+}
+
+/// Everything a device can be read from this frame.
+pub struct Devices<'a> {
+    pub keys: &'a Input<KeyCode>,
+    pub pad: &'a ActivePad,
+    pub axes: &'a Axis<GamepadAxis>,
+    pub buttons: &'a Input<GamepadButton>,
+    /// In world units.
+    pub cursor: Option<Vec2>,
+    /// The first finger on the screen, in world units.
+    pub touch: Option<Vec2>,
+    /// Read the left-hand keys from the arrows and the other way round.
+    pub mirrored: bool,
+}
+
+/// Which device each local player uses. There is one local player; a two-player mode would add a slot here.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InputAssignment {
+    pub player: InputDevice,
+}
+
+impl Default for InputAssignment {
+    fn default() -> Self {
+        Self { player: InputDevice::Any }
+    }
+}
@@ -0,0 +1,127 @@
+use std::fs;
+
+use bevy::log::warn;
+use serde::{Deserialize, Serialize};
+
+use super::{config::GameConfig, hazards::HazardConfig, obstacles::ObstacleConfig, portals::PortalPairConfig};
+
+/// Where court layouts are read from, one `.ron` file per layout.
+pub const COURTS_DIR: &str = "assets/courts";
+
+/// Colours of the court, as `(r, g, b)` from 0 to 1.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CourtTheme {
+    pub court: (f32, f32, f32),
+    pub goal: (f32, f32, f32),
+}
+
+impl Default for CourtTheme {
+    fn default() -> Self {
+        Self {
+            court: (0.1, 0.1, 0.1),
+            goal: (0.6, 0.15, 0.15),
+        }
+    }
+}
+
+/// A court from `assets/courts/`. Anything a file leaves out is the classic court's.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CourtLayout {
+    pub name: String,
+    pub court_width: f32,
+    pub court_height: f32,
+    pub goal_height: f32,
+    pub paddle_offset: f32,
+    pub obstacles: Vec<ObstacleConfig>,
+    pub hazards: Vec<HazardConfig>,
+    pub portals: Vec<PortalPairConfig>,
+    pub theme: CourtTheme,
+}
+
+impl Default for CourtLayout {
+    fn default() -> Self {
+        Self::of(&GameConfig::default())
+    }
+}
+
+impl CourtLayout {
+    /// The layout `config` is currently played on.
+    pub fn of(config: &GameConfig) -> Self {
+        Self {
+            name: config.court.clone(),
+            court_width: config.court_width,
+            court_height: config.court_height,
+            goal_height: config.goal_height,
+            paddle_offset: config.paddle_offset,
+            obstacles: config.obstacles.clone(),
+            hazards: config.hazards.clone(),
+            portals: config.portals.clone(),
+            theme: config.court_theme.clone(),
+        }
+    }
+
+    pub fn apply(&self, config: &mut GameConfig) {
+        config.court = self.name.clone();
+        config.court_width = self.court_width;
+        config.court_height = self.court_height;
+        config.goal_height = self.goal_height;
+        config.paddle_offset = self.paddle_offset;
+        config.obstacles = self.obstacles.clone();
+        config.hazards = self.hazards.clone();
+        config.portals = self.portals.clone();
+        config.court_theme = self.theme.clone();
+    }
+}
+
+/// The layouts in `assets/courts/`, ordered by file name, for picking from the main menu.
+#[derive(Default)]
+pub struct CourtLayouts(pub Vec<CourtLayout>);
+
+impl CourtLayouts {
+    /// Reads every layout, skipping (and warning about) files that don't parse.
+    pub fn load() -> Self {
+        let mut paths: Vec<_> = match fs::read_dir(COURTS_DIR) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.extension().map_or(false, |ext| ext == "ron"))
+                .collect(),
+            Err(e) => {
+                warn!("could not read {}: {}", COURTS_DIR, e);
+                Vec::new()
+            }
+        };
+        paths.sort();
+
+        let layouts = paths.iter()
+            .filter_map(|path| {
+                let layout = fs::read_to_string(path).map_err(|e| e.to_string())
+                    .and_then(|data| ron::from_str::<CourtLayout>(&data).map_err(|e| e.to_string()));
+                match layout {
+                    Ok(layout) => Some(layout),
+                    Err(e) => {
+                        warn!("skipping court {}: {}", path.display(), e);
+                        None
+                    }
+                }
+            })
+            .collect();
+        Self(layouts)
+    }
+
+    /// The layout `delta` steps from the one named `current`, wrapping around.
+    pub fn step(&self, current: &str, delta: i32) -> Option<&CourtLayout> {
+        if self.0.is_empty() {
+            return None;
+        }
+        let len = self.0.len() as i32;
+        let index = match self.0.iter().position(|layout| layout.name == current) {
+            Some(index) => index as i32 + delta,
+            // a court that isn't in the list starts from the first one
+            None if delta < 0 => len - 1,
+            None => 0,
+        };
+        Some(&self.0[index.rem_euclid(len) as usize])
+    }
+}
@@ -0,0 +1,101 @@
+use std::{fmt::Write, fs, panic, sync::{Arc, Mutex}, time::{SystemTime, UNIX_EPOCH}};
+
+use bevy::ecs::{entity::Entities, prelude::*};
+
+use super::{Ball, GameState, MatchTick, config::GameConfig, event_log::EventLog, replay::{self, ReplayRecorder}};
+
+pub const CRASH_DIR: &str = "crashes";
+/// Most recent event log entries put in a report.
+const LOG_ENTRIES: usize = 50;
+
+/// What goes into a crash report. The panic hook can't reach the world, so this is copied out of it every frame.
+#[derive(Default)]
+struct CrashContext {
+    events: Vec<String>,
+    config: String,
+    seed: Option<u64>,
+    state: String,
+    tick: u32,
+    entities: u32,
+    balls: usize,
+}
+
+impl CrashContext {
+    fn report(&self, panic: &str) -> String {
+        let mut report = String::new();
+        let _ = writeln!(report, "panic: {}", panic);
+        let _ = writeln!(report, "version: {}", env!("CARGO_PKG_VERSION"));
+        let _ = writeln!(report, "state: {}", self.state);
+        let _ = writeln!(report, "match tick: {}", self.tick);
+        match self.seed {
+            Some(seed) => { let _ = writeln!(report, "seed: {}", seed); }
+            None => { let _ = writeln!(report, "seed: none, no match was being recorded"); }
+        }
+        let _ = writeln!(report, "entities: {} ({} balls)", self.entities, self.balls);
+        let _ = writeln!(report, "\nlast {} events:", self.events.len());
+        for event in self.events.iter() {
+            let _ = writeln!(report, "  {}", event);
+        }
+        let _ = writeln!(report, "\nconfig:\n{}", self.config);
+        report
+    }
+}
+
+pub struct CrashReporter(Arc<Mutex<CrashContext>>);
+
+fn save_report(report: &str) -> Result<String, String> {
+    fs::create_dir_all(CRASH_DIR).map_err(|e| e.to_string())?;
+    let secs = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default();
+    let path = format!("{}/crash_{}.txt", CRASH_DIR, replay::timestamp(secs));
+    fs::write(&path, report).map_err(|e| e.to_string())?;
+    Ok(path)
+}
+
+/// Installs a panic hook that writes a crash report to `CRASH_DIR` before the default hook prints the panic.
+pub fn install() -> CrashReporter {
+    let context = Arc::new(Mutex::new(CrashContext::default()));
+    let hook_context = context.clone();
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        // a panic while the context is being updated leaves it locked on this thread
+        let report = match hook_context.try_lock() {
+            Ok(context) => context.report(&info.to_string()),
+            Err(_) => format!("panic: {}\n(no game state, it was being updated)\n", info),
+        };
+        match save_report(&report) {
+            Ok(path) => eprintln!("crash report saved to {}", path),
+            Err(e) => eprintln!("couldn't save crash report: {}", e),
+        }
+        default_hook(info);
+    }));
+    CrashReporter(context)
+}
+
+pub fn update_crash_context(
+    reporter : Res<CrashReporter>,
+    log : Res<EventLog>,
+    recorder : Res<ReplayRecorder>,
+    state : Res<State<GameState>>,
+    tick : Res<MatchTick>,
+    balls : Query<(), With<Ball>>,
+    entities : &Entities,
+    config : Res<GameConfig>,
+) {
+    let mut context = match reporter.0.lock() {
+        Ok(context) => context,
+        Err(_) => return,
+    };
+    if log.is_changed() {
+        context.events = log.latest(LOG_ENTRIES)
+            .map(|entry| format!("{:.3} {:?}", entry.time, entry.event))
+            .collect();
+    }
+    if config.is_changed() {
+        context.config = ron::ser::to_string_pretty(&*config, Default::default()).unwrap_or_default();
+    }
+    context.seed = recorder.seed();
+    context.state = format!("{:?}", state.current());
+    context.tick = tick.0;
+    context.entities = entities.len();
+    context.balls = balls.iter().count();
+}
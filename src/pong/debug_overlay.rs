@@ -0,0 +1,181 @@
+#[cfg(feature = "diagnostics")]
+use bevy::diagnostic::{Diagnostics, FrameTimeDiagnosticsPlugin};
+use bevy::{core::prelude::*, ecs::{archetype::Archetypes, component::Components, entity::Entities, prelude::*}, input::prelude::*, text::prelude::*, transform::prelude::*};
+
+use super::{Ball, Paddle, UiFont, breakout::{Brick, Particle}, config::GameConfig, event_log::{EventLog, GameEvent}, profiler::SystemTimings};
+
+const TOGGLE_KEY: KeyCode = KeyCode::F3;
+/// Seconds between refreshes, so the numbers can be read.
+const REFRESH_INTERVAL: f32 = 0.5;
+const MARGIN: f32 = 16.;
+const OVERLAY_Z: f32 = 60.;
+const FONT_SIZE: f32 = 20.;
+
+/// Debug statistics, toggled with F3.
+pub struct DebugOverlay {
+    visible: bool,
+    timer: Timer,
+    ball_spawns: usize,
+    ball_despawns: usize,
+    particle_spawns: usize,
+    particle_despawns: usize,
+}
+
+impl Default for DebugOverlay {
+    fn default() -> Self {
+        Self {
+            visible: false,
+            timer: Timer::from_seconds(REFRESH_INTERVAL, true),
+            ball_spawns: 0,
+            ball_despawns: 0,
+            particle_spawns: 0,
+            particle_despawns: 0,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct DebugText;
+
+pub fn spawn_debug_overlay(
+    mut commands: Commands,
+    font: Res<UiFont>,
+) {
+    commands.spawn_bundle(Text2dBundle {
+        text: Text::with_section(
+            "", TextStyle {
+                font: font.0.clone(),
+                font_size: FONT_SIZE,
+                color: bevy::render::color::Color::WHITE,
+            }, TextAlignment {
+                vertical: VerticalAlign::Top,
+                horizontal: HorizontalAlign::Left,
+            }),
+        ..Default::default()
+    })
+        .insert(DebugText);
+}
+
+pub fn toggle_debug_overlay(
+    input : Res<Input<KeyCode>>,
+    mut overlay : ResMut<DebugOverlay>,
+    mut texts : Query<&mut Text, With<DebugText>>,
+) {
+    if !input.just_pressed(TOGGLE_KEY) {
+        return;
+    }
+    overlay.visible = !overlay.visible;
+    if !overlay.visible {
+        for mut text in texts.iter_mut() {
+            text.sections[0].value.clear();
+        }
+    }
+}
+
+/// Counts what was spawned and despawned since the last refresh, to spot entity leaks.
+pub fn count_spawns(
+    mut overlay : ResMut<DebugOverlay>,
+    new_balls : Query<(), Added<Ball>>,
+    new_particles : Query<(), Added<Particle>>,
+    removed_balls : RemovedComponents<Ball>,
+    removed_particles : RemovedComponents<Particle>,
+) {
+    if !overlay.visible {
+        return;
+    }
+    overlay.ball_spawns += new_balls.iter().count();
+    overlay.particle_spawns += new_particles.iter().count();
+    overlay.ball_despawns += removed_balls.iter().count();
+    overlay.particle_despawns += removed_particles.iter().count();
+}
+
+/// Rough bytes of component data held by every archetype, not counting heap allocations the
+/// components own.
+fn estimate_memory(archetypes: &Archetypes, components: &Components) -> usize {
+    archetypes.iter()
+        .map(|archetype| {
+            let per_entity: usize = archetype.components()
+                .filter_map(|id| components.get_info(id))
+                .map(|info| info.layout().size())
+                .sum();
+            per_entity * archetype.len()
+        })
+        .sum()
+}
+
+pub fn update_debug_overlay(
+    mut overlay : ResMut<DebugOverlay>,
+    mut texts : Query<(&mut Text, &mut Transform), With<DebugText>>,
+    balls : Query<(), With<Ball>>,
+    paddles : Query<(), With<Paddle>>,
+    particles : Query<(), With<Particle>>,
+    bricks : Query<(), With<Brick>>,
+    ui : Query<(), With<Text>>,
+    entities : &Entities,
+    archetypes : &Archetypes,
+    components : &Components,
+    timings : Res<SystemTimings>,
+    log : Res<EventLog>,
+    #[cfg(feature = "diagnostics")]
+    diagnostics : Res<Diagnostics>,
+    config : Res<GameConfig>,
+    time : Res<Time>,
+) {
+    if !overlay.visible || !overlay.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let seconds = overlay.timer.duration().as_secs_f32();
+    let total = entities.len() as usize;
+    let ball_count = balls.iter().count();
+    let paddle_count = paddles.iter().count();
+    let particle_count = particles.iter().count();
+    let brick_count = bricks.iter().count();
+    let ui_count = ui.iter().count();
+    let other = total.saturating_sub(ball_count + paddle_count + particle_count + brick_count + ui_count);
+
+    let mut value = format!(
+        "entities {} ({} archetypes, ~{} KiB of components)\n\
+         balls {} (+{:.0}/s -{:.0}/s)\n\
+         particles {} (+{:.0}/s -{:.0}/s)\n\
+         paddles {}  bricks {}  ui {}  other {}",
+        total, archetypes.len(), estimate_memory(archetypes, components) / 1024,
+        ball_count, overlay.ball_spawns as f32 / seconds, overlay.ball_despawns as f32 / seconds,
+        particle_count, overlay.particle_spawns as f32 / seconds, overlay.particle_despawns as f32 / seconds,
+        paddle_count, brick_count, ui_count, other,
+    );
+    let (mut serves, mut hits, mut points) = (0, 0, 0);
+    for entry in log.since(time.seconds_since_startup() - seconds as f64) {
+        match entry.event {
+            GameEvent::Serve => serves += 1,
+            GameEvent::PaddleHit(_) => hits += 1,
+            GameEvent::Score(_) => points += 1,
+        }
+    }
+    value.push_str(&format!("\nserves {:.1}/s  hits {:.1}/s  points {:.1}/s",
+        serves as f32 / seconds, hits as f32 / seconds, points as f32 / seconds));
+    if let Some(last) = log.latest(1).next() {
+        value.push_str(&format!("\nlast event {:?} at {:.1}s", last.event, last.time));
+    }
+    // render extraction runs in the render app and only shows up as part of the frame time
+    #[cfg(feature = "diagnostics")]
+    if let Some(frame) = diagnostics.get(FrameTimeDiagnosticsPlugin::FRAME_TIME).and_then(|d| d.average()) {
+        value.push_str(&format!("\nframe {:.2} ms", frame * 1000.));
+    }
+    for (name, ms) in timings.averages() {
+        value.push_str(&format!("\n  {} {:.2} ms", name, ms));
+    }
+
+    overlay.ball_spawns = 0;
+    overlay.ball_despawns = 0;
+    overlay.particle_spawns = 0;
+    overlay.particle_despawns = 0;
+
+    let court = config.court_half();
+    for (mut text, mut t) in texts.iter_mut() {
+        text.sections[0].value = value.clone();
+        t.translation.x = -court.x + MARGIN;
+        t.translation.y = court.y - MARGIN;
+        t.translation.z = OVERLAY_Z;
+    }
+}
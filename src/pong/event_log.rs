@@ -0,0 +1,81 @@
+use std::collections::VecDeque;
+
+use super::Who;
+
+const DEFAULT_CAPACITY: usize = 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GameEvent {
+    Serve,
+    PaddleHit(Who),
+    Score(Who),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct LogEntry {
+    /// Seconds since startup.
+    pub time: f64,
+    pub event: GameEvent,
+}
+
+/// Bounded, timestamped record of gameplay events. Once full, the oldest entries are dropped.
+pub struct EventLog {
+    entries: VecDeque<LogEntry>,
+    capacity: usize,
+    /// Entries ever pushed, including those since dropped.
+    pushed: usize,
+}
+
+impl Default for EventLog {
+    fn default() -> Self {
+        Self::with_capacity(DEFAULT_CAPACITY)
+    }
+}
+
+impl EventLog {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::with_capacity(capacity),
+            capacity,
+            pushed: 0,
+        }
+    }
+
+    pub fn push(&mut self, time: f64, event: GameEvent) {
+        self.pushed += 1;
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(LogEntry { time, event });
+    }
+
+    /// All retained entries, oldest first.
+    pub fn iter(&self) -> impl Iterator<Item = &LogEntry> {
+        self.entries.iter()
+    }
+
+    /// Entries recorded at or after `time`.
+    pub fn since(&self, time: f64) -> impl Iterator<Item = &LogEntry> {
+        self.entries.iter().filter(move |e| e.time >= time)
+    }
+
+    /// How many entries were ever pushed. Passed back to `pushed_since` to read only what is new.
+    pub fn pushed(&self) -> usize {
+        self.pushed
+    }
+
+    /// Entries pushed after the log had seen `pushed` of them, oldest first. Unlike `since`, this
+    /// tells apart entries pushed by several fixed ticks run within the same frame.
+    pub fn pushed_since(&self, pushed: usize) -> impl Iterator<Item = &LogEntry> {
+        let new = self.pushed.saturating_sub(pushed).min(self.entries.len());
+        self.entries.iter().skip(self.entries.len() - new)
+    }
+
+    /// The `n` most recent entries, oldest first.
+    pub fn latest(&self, n: usize) -> impl Iterator<Item = &LogEntry> {
+        self.entries.iter().skip(self.entries.len().saturating_sub(n))
+    }
+}
@@ -0,0 +1,77 @@
+use bevy::ecs::prelude::*;
+
+use super::{ExitScreenEvent, Who};
+
+/// The systems that act on a ball going out. Each is handed every exit exactly once.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExitReader { Score, Despawn }
+
+struct PendingExit {
+    event: ExitScreenEvent,
+    scored: bool,
+    despawned: bool,
+}
+
+/// Balls that went out, queued by hand rather than sent as `Events`: events only live for two
+/// updates, so a reader in another schedule than `ball_bounce` could miss an exit or, reading a
+/// ball that is still on its way out, count it twice. Here an exit stays queued until every
+/// reader has taken it.
+#[derive(Default)]
+pub struct ExitQueue {
+    pending: Vec<PendingExit>,
+}
+
+impl ExitQueue {
+    /// Queues `ball` going out on `side`. A ball that is already queued is not queued again, however
+    /// many ticks it overlaps the goal for before it is despawned.
+    pub fn push(&mut self, ball: Entity, side: Who) {
+        if self.contains(ball) {
+            return;
+        }
+        self.pending.push(PendingExit { event: ExitScreenEvent(ball, side), scored: false, despawned: false });
+    }
+
+    /// The exits `reader` hasn't taken yet, in the order they happened.
+    pub fn take(&mut self, reader: ExitReader) -> Vec<ExitScreenEvent> {
+        let mut taken = Vec::new();
+        for exit in self.pending.iter_mut() {
+            let seen = match reader {
+                ExitReader::Score => &mut exit.scored,
+                ExitReader::Despawn => &mut exit.despawned,
+            };
+            if !*seen {
+                *seen = true;
+                taken.push(exit.event);
+            }
+        }
+        taken
+    }
+
+    /// Forgets the exits every reader has taken.
+    pub fn retire(&mut self) {
+        self.pending.retain(|exit| !(exit.scored && exit.despawned));
+    }
+
+    pub fn contains(&self, ball: Entity) -> bool {
+        self.pending.iter().any(|exit| exit.event.0 == ball)
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.pending.clear();
+    }
+}
+
+/// Runs once both readers have had their turn on the tick.
+pub fn retire_exits(
+    mut exits : ResMut<ExitQueue>,
+) {
+    exits.retire();
+}
@@ -0,0 +1,52 @@
+use bevy::{ecs::prelude::*, math::{Vec2, Vec3}, render::{color::Color, view::Visibility}, sprite, transform::prelude::*};
+
+use super::{Ball, BallSprite, MatchEntity, Who, config::GameConfig, sets::Sets};
+
+/// Above the balls and paddles, below the scores and HUD.
+const FOG_Z: f32 = 1.;
+const FOG_ALPHA: f32 = 0.9;
+/// How far ahead of the leading ball the fog stays, so the ball itself is never in it.
+const CLEARANCE: f32 = 30.;
+
+/// Covers the bot's half of the court beyond the ball furthest into it.
+#[derive(Component)]
+pub struct Fog;
+
+pub fn spawn_fog(
+    mut commands : Commands,
+    sprite : Res<BallSprite>,
+    config : Res<GameConfig>,
+) {
+    let mut fog = sprite.tinted(Color::rgba(0., 0., 0., FOG_ALPHA), Vec2::ZERO, Vec3::new(0., 0., FOG_Z));
+    fog.visibility = Visibility { is_visible: config.fog_of_war };
+    commands.spawn_bundle(fog).insert(Fog).insert(MatchEntity);
+}
+
+/// Rolls the fog back ahead of the balls once they cross the centre line, so the bot's end only
+/// shows once a ball gets there.
+pub fn update_fog(
+    balls : Query<&Transform, With<Ball>>,
+    mut fog : Query<(&mut sprite::Sprite, &mut Transform, &mut Visibility), (With<Fog>, Without<Ball>)>,
+    sets : Res<Sets>,
+    config : Res<GameConfig>,
+) {
+    let court = config.court_half();
+    let far = sets.side_of(Who::OPPONENT);
+    let reach = balls.iter()
+        .map(|t| t.translation.x * far)
+        .fold(0., f32::max);
+    let edge = (reach + CLEARANCE).clamp(0., court.x);
+    let width = court.x - edge;
+
+    for (mut sprite, mut t, mut visibility) in fog.iter_mut() {
+        let visible = config.fog_of_war && width > 0.;
+        if visibility.is_visible != visible {
+            visibility.is_visible = visible;
+        }
+        if !visible {
+            continue;
+        }
+        sprite.custom_size = Some(Vec2::new(width, court.y * 2.));
+        t.translation.x = far * (edge + width / 2.);
+    }
+}
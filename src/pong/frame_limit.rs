@@ -0,0 +1,33 @@
+use std::{thread, time::{Duration, Instant}};
+
+use bevy::ecs::prelude::*;
+
+use super::config::GameConfig;
+
+/// Frame caps offered in the settings menu, 0 meaning uncapped.
+pub const FRAME_LIMITS: [u32; 6] = [0, 30, 60, 120, 144, 240];
+
+pub struct FrameLimiter {
+    last_frame: Instant,
+}
+
+impl Default for FrameLimiter {
+    fn default() -> Self {
+        Self { last_frame: Instant::now() }
+    }
+}
+
+/// Sleeps out the rest of the frame when `frame_limit` is set. Runs in the last stage.
+pub fn limit_frame_rate(
+    mut limiter : ResMut<FrameLimiter>,
+    config : Res<GameConfig>,
+) {
+    if config.frame_limit > 0 {
+        let frame_time = Duration::from_secs_f64(1.0 / config.frame_limit as f64);
+        let elapsed = limiter.last_frame.elapsed();
+        if elapsed < frame_time {
+            thread::sleep(frame_time - elapsed);
+        }
+    }
+    limiter.last_frame = Instant::now();
+}
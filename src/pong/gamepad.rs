@@ -0,0 +1,92 @@
+use bevy::{ecs::prelude::*, input::{gamepad::{Gamepad, GamepadAxis, GamepadAxisType, GamepadButton, GamepadButtonType, GamepadEvent, GamepadEventType}, prelude::*}, log::info};
+
+use super::{GameState, config::GameConfig, controls::InputDevice};
+
+/// Stick travel around the centre that doesn't count as input.
+const DEADZONE: f32 = 0.3;
+
+/// The gamepad the controls read: the first one connected, until it's unplugged and another
+/// connected one takes over.
+#[derive(Default)]
+pub struct ActivePad {
+    gamepad: Option<Gamepad>,
+    /// Every pad plugged in, in the order they connected.
+    connected: Vec<Gamepad>,
+    /// The active pad was unplugged mid-match, for the pause screen to say so.
+    pub disconnected: bool,
+}
+
+impl ActivePad {
+    /// -1, 0 or 1 for down, nothing or up on the d-pad or left stick. The stick is read digitally like the keys, so
+    /// replays record it exactly.
+    pub fn vertical(&self, axes: &Axis<GamepadAxis>, buttons: &Input<GamepadButton>) -> i8 {
+        self.gamepad.map_or(0, |gamepad| vertical(gamepad, axes, buttons))
+    }
+
+    /// `button` on the active pad, if there is one.
+    pub fn button(&self, button: GamepadButtonType) -> Option<GamepadButton> {
+        self.gamepad.map(|gamepad| GamepadButton(gamepad, button))
+    }
+}
+
+/// `ActivePad::vertical` for any pad.
+pub fn vertical(gamepad: Gamepad, axes: &Axis<GamepadAxis>, buttons: &Input<GamepadButton>) -> i8 {
+    if buttons.pressed(GamepadButton(gamepad, GamepadButtonType::DPadUp)) {
+        return 1;
+    }
+    if buttons.pressed(GamepadButton(gamepad, GamepadButtonType::DPadDown)) {
+        return -1;
+    }
+    let y = axes.get(GamepadAxis(gamepad, GamepadAxisType::LeftStickY)).unwrap_or(0.);
+    if y > DEADZONE { 1 } else if y < -DEADZONE { -1 } else { 0 }
+}
+
+/// Picks up newly connected pads and pauses the match when the one the player uses is unplugged,
+/// moving the controls over to another connected pad if there is one.
+pub fn track_gamepads(
+    mut events : EventReader<GamepadEvent>,
+    mut pad : ResMut<ActivePad>,
+    mut state : ResMut<State<GameState>>,
+    config : Res<GameConfig>,
+) {
+    for GamepadEvent(gamepad, event) in events.iter() {
+        match event {
+            GamepadEventType::Connected => {
+                if !pad.connected.contains(gamepad) {
+                    pad.connected.push(*gamepad);
+                }
+                if pad.gamepad.is_none() {
+                    info!("using gamepad {:?}", gamepad);
+                    pad.gamepad = Some(*gamepad);
+                    pad.disconnected = false;
+                }
+            }
+            GamepadEventType::Disconnected => {
+                pad.connected.retain(|connected| connected != gamepad);
+                let active = pad.gamepad == Some(*gamepad);
+                if active {
+                    info!("gamepad {:?} disconnected", gamepad);
+                    pad.gamepad = pad.connected.first().copied();
+                    if let Some(fallback) = pad.gamepad {
+                        info!("using gamepad {:?}", fallback);
+                    }
+                }
+                let used = match config.input.player {
+                    InputDevice::Pad(index) => index == gamepad.0,
+                    device => active && device.uses_gamepad(),
+                };
+                if used && *state.current() == GameState::Playing {
+                    pad.disconnected = true;
+                    let _ = state.push(GameState::Paused);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+pub fn clear_disconnect_notice(
+    mut pad : ResMut<ActivePad>,
+) {
+    pad.disconnected = false;
+}
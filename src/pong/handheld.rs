@@ -0,0 +1,41 @@
+use std::env;
+
+use bevy::{ecs::prelude::*, math::Vec2};
+
+use super::{UiFont, config::{GameConfig, GraphicsQuality}, controls::InputDevice, menu::{MenuAction, MenuCursor, spawn_menu_items}, screens::spawn_screen_text};
+
+/// Most balls served per match under the preset.
+const HANDHELD_BALLS: i64 = 1_000;
+/// Battery saver: the fixed tick keeps the physics the same at any frame rate.
+const HANDHELD_FRAME_LIMIT: u32 = 30;
+/// Text is read from arm's length on a 1280x800 screen.
+const HANDHELD_TEXT_SCALE: f32 = 1.25;
+
+/// Whether the game is running somewhere the gamepad is the only input. Steam sets `SteamDeck`
+/// on the Steam Deck.
+pub fn detected() -> bool {
+    env::var_os("SteamDeck").is_some()
+}
+
+/// Settings for a handheld: the gamepad drives the paddle, the field stays small and the frame
+/// rate is capped to save battery.
+pub fn apply_preset(config: &mut GameConfig) {
+    config.input.player = InputDevice::Gamepad;
+    config.graphics_quality = GraphicsQuality::Low;
+    config.balls_amount = config.balls_amount.min(HANDHELD_BALLS);
+    config.frame_limit = HANDHELD_FRAME_LIMIT;
+    config.text_scale = HANDHELD_TEXT_SCALE;
+}
+
+/// Asked on the first launch on a handheld, before the main menu.
+pub fn spawn_handheld_prompt(
+    mut commands : Commands,
+    mut cursor : ResMut<MenuCursor>,
+    font : Res<UiFont>,
+) {
+    spawn_screen_text(&mut commands, &font, "Handheld detected", Vec2::new(0., 150.), 80.);
+    let line = format!("Gamepad controls, fewer balls and a {} fps cap to save battery", HANDHELD_FRAME_LIMIT);
+    spawn_screen_text(&mut commands, &font, &line, Vec2::new(0., 80.), 30.);
+    spawn_menu_items(&mut commands, &font, &mut cursor,
+        &[MenuAction::HandheldPreset, MenuAction::KeepDefaults], 0.);
+}
@@ -0,0 +1,77 @@
+use bevy::{ecs::prelude::*, math::{Vec2, Vec3}, render::color::Color, transform::prelude::*};
+use serde::{Deserialize, Serialize};
+
+use super::{Ball, BallSprite, MatchEntity, Velocity, config::GameConfig, profiler::SystemTimings};
+
+/// Closest distance used for the force, so a ball passing through the centre isn't flung away.
+const MIN_DISTANCE: f32 = 20.;
+const HAZARD_SIZE: f32 = 24.;
+const HAZARD_Z: f32 = -0.5;
+
+/// A gravity well (positive strength) or repulsor (negative strength) from `config.ron`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct HazardConfig {
+    pub x: f32,
+    pub y: f32,
+    /// The pull is `strength / distance²` pixels per second squared.
+    pub strength: f32,
+}
+
+#[derive(Component)]
+pub struct Hazard {
+    strength: f32,
+}
+
+/// Spawns the hazards for a match, and respawns them when the config changes mid-match.
+pub fn sync_hazards(
+    mut commands : Commands,
+    hazards : Query<Entity, With<Hazard>>,
+    sprite : Res<BallSprite>,
+    config : Res<GameConfig>,
+) {
+    // on entering the match there is nothing to replace yet and the config counts as unchanged
+    if !config.is_changed() && hazards.iter().next().is_some() {
+        return;
+    }
+    for e in hazards.iter() {
+        commands.entity(e).despawn();
+    }
+
+    for hazard in config.hazards.iter() {
+        let color = if hazard.strength >= 0. {
+            Color::rgba(0.5, 0.2, 0.9, 0.8)
+        } else {
+            Color::rgba(0.2, 0.8, 0.9, 0.8)
+        };
+        commands.spawn_bundle(sprite.tinted(color, Vec2::splat(HAZARD_SIZE), Vec3::new(hazard.x, hazard.y, HAZARD_Z)))
+            .insert(Hazard { strength: hazard.strength })
+            .insert(MatchEntity);
+    }
+}
+
+/// Pulls balls towards wells and pushes them away from repulsors, once per physics tick.
+pub fn apply_hazard_forces(
+    hazards : Query<(&Hazard, &Transform)>,
+    mut balls : Query<(&mut Velocity, &Transform), With<Ball>>,
+    mut timings : ResMut<SystemTimings>,
+    config : Res<GameConfig>,
+) {
+    let _timed = timings.time("apply_hazard_forces");
+    let hazards: Vec<(f32, Vec2)> = hazards.iter()
+        .map(|(hazard, t)| (hazard.strength, t.translation.truncate()))
+        .collect();
+    if hazards.is_empty() {
+        return;
+    }
+
+    // velocities are in pixels per tick, so an acceleration per second squared is scaled twice
+    let per_tick_squared = (config.timestep() * config.timestep()) as f32;
+    for (mut v, t) in balls.iter_mut() {
+        let position = t.translation.truncate();
+        for (strength, center) in hazards.iter() {
+            let offset = *center - position;
+            let distance = offset.length().max(MIN_DISTANCE);
+            v.0 += offset / distance * (*strength * per_tick_squared / (distance * distance));
+        }
+    }
+}
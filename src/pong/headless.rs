@@ -0,0 +1,167 @@
+use bevy::{app::prelude::*, asset::Handle, core::Time, ecs::{prelude::*, system::{IntoSystem, System}}, input::prelude::*, render::color::Color, transform::prelude::*};
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg32;
+
+use super::{Ball, BallCount, BallSprite, BonusEvent, GameRng, PaddleHitEvent, GameState, MatchTick, Paddle, Score, ScoreEvent, ServeEvent, UiFont, Velocity, WallBounceEvent, Who, breakout, combo::Combo, config::GameConfig, event_log::EventLog, exits::ExitQueue, hazards, obstacles, portals, power_shot::ScreenShake, profiler::SystemTimings, replay::{self, InputChange, Replay, ReplayHeader, ReplayRecorder, REPLAY_VERSION}, replay_viewer::ReplayViewer, rewind::RewindBuffer, save::{self, PendingLoad, SaveFile}, serve::Serve, sets::Sets, sim_clock::SimClock, spawn_background, speedometer::BallSpeeds, spawn_paddles, transition::Transition};
+
+/// Plays a replay back without a window or renderer, one physics tick per `step`, for tests and
+/// tools checking the simulation.
+pub struct Simulation {
+    app: App,
+}
+
+impl Simulation {
+    /// Sets up a match like `GameState::Replay` does: seeded from `replay`, with its inputs
+    /// driving the player paddle.
+    pub fn new(config: GameConfig, replay: Replay) -> Self {
+        let mut clock = SimClock::default();
+        clock.timestep = config.timestep();
+        let serve = Serve::first(replay.header.first_server);
+        let mut app = App::new();
+        app
+            .insert_resource(State::new(GameState::Replay))
+            .insert_resource(Time::default())
+            .insert_resource(GameRng(Pcg32::seed_from_u64(replay.header.seed)))
+            .insert_resource(ReplayViewer::watching(replay))
+            .insert_resource(BallCount(0))
+            .insert_resource(BallSprite { texture: Handle::default(), color: Color::WHITE })
+            .insert_resource(UiFont(Handle::default()))
+            .insert_resource(MatchTick::default())
+            .insert_resource(EventLog::default())
+            .insert_resource(SystemTimings::default())
+            .insert_resource(ScreenShake::default())
+            .insert_resource(Combo::default())
+            .insert_resource(BallSpeeds::default())
+            .insert_resource(Sets::default())
+            .insert_resource(serve)
+            .insert_resource(ReplayRecorder::default())
+            .insert_resource(RewindBuffer::default())
+            .insert_resource(Transition::default())
+            .insert_resource(clock)
+            .insert_resource(config)
+            .add_event::<ScoreEvent>()
+            .insert_resource(ExitQueue::default())
+            .insert_resource(Input::<KeyCode>::default())
+            .insert_resource(PendingLoad::default())
+            .insert_resource(SaveFile::default())
+            .add_event::<BonusEvent>()
+            .add_event::<PaddleHitEvent>()
+            .add_event::<WallBounceEvent>()
+            .add_event::<ServeEvent>()
+            .add_startup_system(spawn_paddles)
+            .add_startup_system(spawn_background)
+            .add_startup_system(breakout::spawn_bricks)
+            .add_startup_system(hazards::sync_hazards)
+            .add_startup_system(portals::sync_portals)
+            .add_startup_system(obstacles::sync_obstacles)
+            .add_system_set(super::simulation_systems())
+            .add_system_set(super::serving_systems());
+        Self { app }
+    }
+
+    /// Runs one physics tick.
+    pub fn step(&mut self) {
+        self.app.update();
+    }
+
+    /// Physics ticks run so far.
+    pub fn tick(&self) -> u32 {
+        self.app.world.get_resource::<MatchTick>().map(|tick| tick.0).unwrap_or_default()
+    }
+
+    /// `(player, bot)` points.
+    pub fn scores(&mut self) -> (i32, i32) {
+        let mut scores = (0, 0);
+        // counters are keyed by the side balls go out on, the opposite of who they score for
+        for score in self.app.world.query::<&Score>().iter(&self.app.world) {
+            match score.0 {
+                Who::OPPONENT => scores.0 = score.1,
+                Who::PLAYER => scores.1 = score.1,
+            }
+        }
+        scores
+    }
+
+    /// Hash of the tick, scores and every ball and paddle position and velocity. Two runs only
+    /// hash the same if they simulated bit for bit the same.
+    pub fn checksum(&mut self) -> u64 {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&self.tick().to_le_bytes());
+        let (player, bot) = self.scores();
+        bytes.extend_from_slice(&player.to_le_bytes());
+        bytes.extend_from_slice(&bot.to_le_bytes());
+
+        let world = &mut self.app.world;
+        let mut bodies = world.query_filtered::<(&Transform, &Velocity), Or<(With<Ball>, With<Paddle>)>>();
+        for (t, v) in bodies.iter(world) {
+            for value in [t.translation.x, t.translation.y, v.0.x, v.0.y] {
+                bytes.extend_from_slice(&value.to_bits().to_le_bytes());
+            }
+        }
+        replay::fnv1a(&bytes)
+    }
+
+    /// Saves the match to `path`, like pressing the save key does.
+    pub fn save(&mut self, path: &str) {
+        let world = &mut self.app.world;
+        world.insert_resource(SaveFile(path.to_string()));
+        world.get_resource_mut::<Input<KeyCode>>().unwrap().press(save::SAVE_KEY);
+        run_once(world, save::save_match);
+        world.insert_resource(Input::<KeyCode>::default());
+    }
+
+    /// Loads the match saved at `path`, like "Continue" does from the main menu. The paddles are
+    /// spawned on the first `step`, so there has to have been one already.
+    pub fn load(&mut self, path: &str) {
+        let world = &mut self.app.world;
+        world.insert_resource(SaveFile(path.to_string()));
+        world.insert_resource(PendingLoad(true));
+        run_once(world, save::load_match);
+    }
+
+    /// `checksum` after each of the replay's ticks.
+    pub fn checksums(config: GameConfig, replay: Replay) -> Vec<u64> {
+        let length = replay.header.length;
+        let mut simulation = Simulation::new(config, replay);
+        let mut checksums = Vec::with_capacity(length as usize);
+        while simulation.tick() < length {
+            simulation.step();
+            checksums.push(simulation.checksum());
+        }
+        checksums
+    }
+}
+
+/// Runs `system` once outside the schedule, applying its commands straight away.
+fn run_once<Params>(world: &mut World, system: impl IntoSystem<(), (), Params>) {
+    let mut system = system.system();
+    system.initialize(world);
+    system.run((), world);
+    system.apply_buffers(world);
+}
+
+/// Ticks between input changes in `scripted_replay`.
+const SCRIPT_INTERVAL: u32 = 30;
+
+/// A replay of `length` ticks from `seed`, with the player paddle moving and charging at random
+/// (picked from the seed too), so runs exercise more than an idle paddle.
+pub fn scripted_replay(config: &GameConfig, seed: u64, length: u32) -> Replay {
+    let mut rng = Pcg32::seed_from_u64(seed ^ 0x5eed);
+    let inputs = (0..length).step_by(SCRIPT_INTERVAL as usize)
+        .map(|tick| InputChange { tick, input: rng.gen_range(-1..=1), charging: rng.gen_bool(0.2) })
+        .collect();
+    Replay {
+        header: ReplayHeader { version: REPLAY_VERSION, config_hash: replay::config_hash(config), seed, length, first_server: Who::PLAYER },
+        inputs,
+        score_ticks: Vec::new(),
+    }
+}
+
+/// The first tick (counted from 0) two runs' checksums differ on, or where the shorter one ends.
+pub fn first_divergence(a: &[u64], b: &[u64]) -> Option<usize> {
+    match a.iter().zip(b.iter()).position(|(a, b)| a != b) {
+        Some(tick) => Some(tick),
+        None if a.len() != b.len() => Some(a.len().min(b.len())),
+        None => None,
+    }
+}
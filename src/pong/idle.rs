@@ -0,0 +1,58 @@
+use bevy::{core::prelude::*, ecs::prelude::*, input::{gamepad::{GamepadAxis, GamepadButton}, prelude::*}, window::CursorMoved};
+
+use super::{GameState, config::GameConfig, gamepad::ActivePad, transition::Transition};
+
+/// Seconds without any input, counted during a match and on the pause screen.
+#[derive(Default)]
+pub struct IdleTimer {
+    idle: f32,
+    /// The match was paused for being left idle, for the pause screen to say so.
+    pub paused: bool,
+}
+
+/// Pauses a match nobody has touched for `GameConfig::idle_pause` seconds, and quits to the menu
+/// after `GameConfig::idle_quit`, so an unattended match can't run up the bot's score.
+pub fn track_idle(
+    mut timer : ResMut<IdleTimer>,
+    mut state : ResMut<State<GameState>>,
+    mut transition : ResMut<Transition>,
+    mut cursor_moved : EventReader<CursorMoved>,
+    keys : Res<Input<KeyCode>>,
+    mouse : Res<Input<MouseButton>>,
+    buttons : Res<Input<GamepadButton>>,
+    axes : Res<Axis<GamepadAxis>>,
+    pad : Res<ActivePad>,
+    touches : Res<Touches>,
+    time : Res<Time>,
+    config : Res<GameConfig>,
+) {
+    let input = cursor_moved.iter().count() > 0
+        || keys.get_pressed().next().is_some()
+        || mouse.get_pressed().next().is_some()
+        || buttons.get_pressed().next().is_some()
+        || pad.vertical(&axes, &buttons) != 0
+        || touches.iter().next().is_some();
+    let counting = matches!(state.current(), GameState::Playing | GameState::Paused);
+    if input || !counting || config.idle_pause <= 0. {
+        timer.idle = 0.;
+        return;
+    }
+
+    timer.idle += time.delta_seconds();
+    match state.current() {
+        GameState::Playing if timer.idle >= config.idle_pause => {
+            timer.paused = true;
+            let _ = state.push(GameState::Paused);
+        }
+        GameState::Paused if config.idle_quit > 0. && timer.idle >= config.idle_pause + config.idle_quit => {
+            transition.fade_to(GameState::Menu);
+        }
+        _ => {}
+    }
+}
+
+pub fn clear_idle_notice(
+    mut timer : ResMut<IdleTimer>,
+) {
+    timer.paused = false;
+}
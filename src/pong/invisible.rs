@@ -0,0 +1,56 @@
+use bevy::{core::prelude::*, ecs::prelude::*, render::view::Visibility};
+
+use super::{Ball, PaddleHitEvent, WallBounceEvent, config::GameConfig};
+
+/// Seconds between flashes.
+const FLASH_INTERVAL: f32 = 1.;
+/// Seconds each flash lasts.
+const FLASH_TIME: f32 = 0.1;
+
+/// A ball under the invisible-ball mutator: hidden except for a flash every second and on every bounce.
+/// Only its visibility changes, so the physics is unaffected.
+#[derive(Component, Default)]
+pub struct InvisibleBall {
+    since_flash: f32,
+}
+
+/// Flags every new ball while `GameConfig::invisible_balls` is on, and unflags them all when it
+/// is turned off, leaving their visibility to the off-screen culling again.
+pub fn flag_invisible_balls(
+    mut commands : Commands,
+    added : Query<Entity, (Added<Ball>, Without<InvisibleBall>)>,
+    flagged : Query<Entity, With<InvisibleBall>>,
+    config : Res<GameConfig>,
+) {
+    if config.invisible_balls {
+        for e in added.iter() {
+            commands.entity(e).insert(InvisibleBall::default());
+        }
+    } else {
+        for e in flagged.iter() {
+            commands.entity(e).remove::<InvisibleBall>();
+        }
+    }
+}
+
+pub fn flash_invisible_balls(
+    mut balls : Query<(&mut InvisibleBall, &mut Visibility)>,
+    mut paddle_hits : EventReader<PaddleHitEvent>,
+    mut wall_bounces : EventReader<WallBounceEvent>,
+    time : Res<Time>,
+) {
+    for ball in paddle_hits.iter().map(|hit| hit.ball).chain(wall_bounces.iter().map(|bounce| bounce.ball)) {
+        if let Ok((mut invisible, _)) = balls.get_mut(ball) {
+            invisible.since_flash = 0.;
+        }
+    }
+
+    for (mut invisible, mut visibility) in balls.iter_mut() {
+        invisible.since_flash = (invisible.since_flash + time.delta_seconds()) % FLASH_INTERVAL;
+        let visible = invisible.since_flash < FLASH_TIME;
+        // only write on change so hidden balls don't get flagged for re-extraction
+        if visibility.is_visible != visible {
+            visibility.is_visible = visible;
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use std::{fs::OpenOptions, sync::Mutex};
+
+use bevy::log::{Level, LogSettings};
+use serde::{Deserialize, Serialize};
+use tracing_subscriber::{EnvFilter, fmt, prelude::*};
+
+/// Logging setup from `config.ron`, overridable on the command line. Only read at startup.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogConfig {
+    /// `error`, `warn`, `info`, `debug` or `trace`.
+    pub level: String,
+    /// Extra per-module directives, e.g. `wgpu=error,pong=debug`.
+    pub filter: String,
+    /// Also write the log to this file, appending to it.
+    pub file: Option<String>,
+    /// Print frame time diagnostics to the log every second.
+    pub diagnostics: bool,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            level: "info".to_string(),
+            filter: "wgpu=error".to_string(),
+            file: None,
+            diagnostics: false,
+        }
+    }
+}
+
+impl LogConfig {
+    /// Applies `--log-level <level>`, `--log-filter <directives>`, `--log-file <path>` or `--log-diagnostics`,
+    /// taking its value from `args`. Returns false for any other argument, or one missing its value.
+    pub fn apply_arg(&mut self, arg: &str, args: &mut impl Iterator<Item = String>) -> bool {
+        match arg {
+            "--log-level" => args.next().map(|level| self.level = level).is_some(),
+            "--log-filter" => args.next().map(|filter| self.filter = filter).is_some(),
+            "--log-file" => args.next().map(|path| self.file = Some(path)).is_some(),
+            "--log-diagnostics" => {
+                self.diagnostics = true;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn level(&self) -> Level {
+        self.level.parse().unwrap_or_else(|_| {
+            eprintln!("unknown log level {}, using info", self.level);
+            Level::INFO
+        })
+    }
+
+    /// Settings for Bevy's log plugin, when logging to the console only.
+    pub fn settings(&self) -> LogSettings {
+        LogSettings {
+            level: self.level(),
+            filter: self.filter.clone(),
+        }
+    }
+
+    /// Sets up logging to both the console and `path`. Bevy's log plugin can't write to a file, so it has to be
+    /// disabled when this succeeds.
+    pub fn init_file_logging(&self, path: &str) -> Result<(), String> {
+        let file = OpenOptions::new().create(true).append(true).open(path).map_err(|e| e.to_string())?;
+        let filter = EnvFilter::try_new(format!("{},{}", self.level(), self.filter)).map_err(|e| e.to_string())?;
+        tracing_subscriber::registry()
+            .with(filter)
+            .with(fmt::layer())
+            .with(fmt::layer().with_ansi(false).with_writer(Mutex::new(file)))
+            .try_init()
+            .map_err(|e| e.to_string())
+    }
+}
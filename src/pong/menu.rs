@@ -0,0 +1,198 @@
+use bevy::{ecs::prelude::*, input::{gamepad::{GamepadButton, GamepadButtonType}, prelude::*}, math::Vec2, text::prelude::*};
+
+use super::{UiFont, config::GameConfig, controls::InputDevice, courts::CourtLayout, gamepad::ActivePad, screens::spawn_screen_text};
+
+const ITEM_SPACING: f32 = 60.0;
+const ITEM_SIZE: f32 = 40.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MenuAction {
+    Play,
+    ContinueSaved,
+    Court,
+    Sandbox,
+    WatchReplay,
+    Restart,
+    Resume,
+    Settings,
+    QuitToMenu,
+    Quit,
+    Back,
+    PaddleSpeed,
+    BotSpeed,
+    Graphics,
+    VSync,
+    FrameLimit,
+    PlayerShape,
+    BotShape,
+    Stamina,
+    Assist,
+    Controls,
+    HandheldPreset,
+    KeepDefaults,
+}
+
+impl MenuAction {
+    fn label(&self, config: &GameConfig) -> String {
+        match self {
+            MenuAction::Play => "Play".to_string(),
+            MenuAction::ContinueSaved => "Continue saved match".to_string(),
+            MenuAction::Court => format!("Court: {}", config.court),
+            MenuAction::Sandbox => "Sandbox".to_string(),
+            MenuAction::WatchReplay => "Watch replay".to_string(),
+            MenuAction::Restart => "Restart".to_string(),
+            MenuAction::Resume => "Resume".to_string(),
+            MenuAction::Settings => "Settings".to_string(),
+            MenuAction::QuitToMenu => "Quit to menu".to_string(),
+            MenuAction::Quit => "Quit".to_string(),
+            MenuAction::Back => "Back".to_string(),
+            MenuAction::PaddleSpeed => format!("Paddle speed: {}", config.paddle_speed),
+            MenuAction::BotSpeed => format!("Bot speed: {}", config.bot_paddle_speed),
+            MenuAction::Graphics => format!("Graphics: {:?}", config.graphics_quality),
+            MenuAction::VSync => format!("VSync: {}", if config.vsync { "On" } else { "Off" }),
+            MenuAction::FrameLimit => match config.frame_limit {
+                0 => "Frame limit: Off".to_string(),
+                fps => format!("Frame limit: {}", fps),
+            },
+            MenuAction::PlayerShape => format!("Paddle shape: {:?}", config.player_paddle_shape),
+            MenuAction::BotShape => format!("Bot shape: {:?}", config.bot_paddle_shape),
+            MenuAction::Stamina => format!("Stamina: {}", if config.stamina { "On" } else { "Off" }),
+            MenuAction::Controls => match config.input.player {
+                InputDevice::Pad(index) => format!("Controls: Gamepad {}", index),
+                device => format!("Controls: {:?}", device),
+            },
+            MenuAction::Assist => format!("Trajectory assist: {}", if config.trajectory_assist { "On" } else { "Off" }),
+            MenuAction::HandheldPreset => "Use the handheld preset".to_string(),
+            MenuAction::KeepDefaults => "Keep the defaults".to_string(),
+        }
+    }
+
+    /// Items of the settings screens and the main menu's court picker.
+    pub fn is_setting(&self) -> bool {
+        matches!(self, MenuAction::Court | MenuAction::PaddleSpeed | MenuAction::BotSpeed | MenuAction::Graphics
+            | MenuAction::VSync | MenuAction::FrameLimit | MenuAction::PlayerShape | MenuAction::BotShape
+            | MenuAction::Stamina | MenuAction::Assist | MenuAction::Controls)
+    }
+
+    /// Copies what this settings item changes from `from` to `to`.
+    pub fn copy_setting(&self, from: &GameConfig, to: &mut GameConfig) {
+        match self {
+            MenuAction::Court => CourtLayout::of(from).apply(to),
+            MenuAction::PaddleSpeed => to.paddle_speed = from.paddle_speed,
+            MenuAction::BotSpeed => to.bot_paddle_speed = from.bot_paddle_speed,
+            MenuAction::Graphics => to.graphics_quality = from.graphics_quality,
+            MenuAction::VSync => to.vsync = from.vsync,
+            MenuAction::FrameLimit => to.frame_limit = from.frame_limit,
+            MenuAction::PlayerShape => to.player_paddle_shape = from.player_paddle_shape,
+            MenuAction::BotShape => to.bot_paddle_shape = from.bot_paddle_shape,
+            MenuAction::Stamina => to.stamina = from.stamina,
+            MenuAction::Assist => to.trajectory_assist = from.trajectory_assist,
+            MenuAction::Controls => to.input.player = from.input.player,
+            _ => {}
+        }
+    }
+}
+
+/// A selectable line of a menu screen, ordered top to bottom by `index`.
+#[derive(Component)]
+pub struct MenuItem {
+    pub index: usize,
+    pub action: MenuAction,
+}
+
+/// Index of the highlighted menu item.
+#[derive(Default)]
+pub struct MenuCursor(pub usize);
+
+/// Sent when a menu item is activated (`delta == 0`) or adjusted left/right (`delta == -1/1`).
+/// Escape sends `MenuAction::Back`. The active pad's d-pad, south and east buttons work like the
+/// arrows, Return and Escape.
+pub struct MenuEvent {
+    pub action: MenuAction,
+    pub delta: i32,
+}
+
+pub fn spawn_menu_items(
+    commands: &mut Commands,
+    font: &UiFont,
+    cursor: &mut MenuCursor,
+    actions: &[MenuAction],
+    top: f32,
+) {
+    for (index, action) in actions.iter().enumerate() {
+        let pos = Vec2::new(0., top - index as f32 * ITEM_SPACING);
+        let e = spawn_screen_text(commands, font, "", pos, ITEM_SIZE);
+        commands.entity(e).insert(MenuItem { index, action: *action });
+    }
+    cursor.0 = 0;
+}
+
+pub fn navigate_menu(
+    mut input : ResMut<Input<KeyCode>>,
+    mut buttons : ResMut<Input<GamepadButton>>,
+    pad : Res<ActivePad>,
+    mut cursor : ResMut<MenuCursor>,
+    mut events : EventWriter<MenuEvent>,
+    items : Query<&MenuItem>,
+) {
+    let count = items.iter().count();
+    if count == 0 {
+        return;
+    }
+
+    let pad_pressed = |button| pad.button(button).map_or(false, |button| buttons.just_pressed(button));
+    if input.just_pressed(KeyCode::Up) || input.just_pressed(KeyCode::W) || pad_pressed(GamepadButtonType::DPadUp) {
+        cursor.0 = (cursor.0 + count - 1) % count;
+    }
+    if input.just_pressed(KeyCode::Down) || input.just_pressed(KeyCode::S) || pad_pressed(GamepadButtonType::DPadDown) {
+        cursor.0 = (cursor.0 + 1) % count;
+    }
+
+    let selected = match items.iter().find(|item| item.index == cursor.0) {
+        Some(item) => item.action,
+        None => return,
+    };
+
+    // keys that act on the menu are consumed so the screen they open doesn't see them as well
+    for (key, action, delta) in [
+        (KeyCode::Return, selected, 0),
+        (KeyCode::Space, selected, 0),
+        (KeyCode::Left, selected, -1),
+        (KeyCode::Right, selected, 1),
+        (KeyCode::Escape, MenuAction::Back, 0),
+    ] {
+        if input.just_pressed(key) {
+            input.reset(key);
+            events.send(MenuEvent { action, delta });
+        }
+    }
+    for (button, action, delta) in [
+        (GamepadButtonType::South, selected, 0),
+        (GamepadButtonType::DPadLeft, selected, -1),
+        (GamepadButtonType::DPadRight, selected, 1),
+        (GamepadButtonType::East, MenuAction::Back, 0),
+    ] {
+        if let Some(button) = pad.button(button).filter(|button| buttons.just_pressed(*button)) {
+            buttons.reset(button);
+            events.send(MenuEvent { action, delta });
+        }
+    }
+}
+
+pub fn update_menu_labels(
+    cursor : Res<MenuCursor>,
+    config : Res<GameConfig>,
+    mut items : Query<(&MenuItem, &mut Text)>,
+) {
+    for (item, mut text) in items.iter_mut() {
+        let label = item.action.label(&config);
+        let section = &mut text.sections[0];
+        if item.index == cursor.0 {
+            section.value = format!("> {} <", label);
+            section.style.color = bevy::render::color::Color::YELLOW;
+        } else {
+            section.value = label;
+            section.style.color = bevy::render::color::Color::WHITE;
+        }
+    }
+}
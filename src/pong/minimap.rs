@@ -0,0 +1,100 @@
+use bevy::{asset::prelude::*, ecs::prelude::*, math::Vec2, render::{render_resource::{Extent3d, TextureDimension, TextureFormat}, texture::Image, view::Visibility}, sprite::{self, SpriteBundle}, transform::prelude::*};
+
+use super::{Ball, BallCount, GameState, config::GameConfig};
+
+const CELLS_X: usize = 64;
+const CELLS_Y: usize = 36;
+const DISPLAY_WIDTH: f32 = 256.;
+const DISPLAY_HEIGHT: f32 = 144.;
+const MARGIN: f32 = 16.;
+const MINIMAP_Z: f32 = 50.;
+/// The heatmap is only worth its screen space once the field is too crowded to read.
+const BALL_THRESHOLD: i32 = 1000;
+
+#[derive(Component)]
+pub struct Minimap;
+pub struct MinimapImage(Handle<Image>);
+
+pub fn spawn_minimap(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let image = images.add(Image::new_fill(
+        Extent3d { width: CELLS_X as u32, height: CELLS_Y as u32, depth_or_array_layers: 1 },
+        TextureDimension::D2,
+        &[0, 0, 0, 0],
+        TextureFormat::Rgba8UnormSrgb,
+    ));
+
+    commands.spawn_bundle(SpriteBundle {
+        sprite: sprite::Sprite {
+            custom_size: Some(Vec2::new(DISPLAY_WIDTH, DISPLAY_HEIGHT)),
+            ..Default::default()
+        },
+        texture: image.clone(),
+        transform: Transform::from_xyz(0., 0., MINIMAP_Z),
+        visibility: Visibility { is_visible: false },
+        ..Default::default()
+    })
+        .insert(Minimap);
+    commands.insert_resource(MinimapImage(image));
+}
+
+/// Keeps the minimap in the top right corner of the court, shown only during crowded matches.
+pub fn place_minimap(
+    mut sprite : Query<(&mut Transform, &mut Visibility), With<Minimap>>,
+    ball_count : Res<BallCount>,
+    state : Res<State<GameState>>,
+    config : Res<GameConfig>,
+) {
+    let court = config.court_half();
+    let in_match = matches!(state.current(), GameState::Playing | GameState::Paused | GameState::SwitchingSides);
+
+    for (mut t, mut visibility) in sprite.iter_mut() {
+        visibility.is_visible = in_match && ball_count.0 >= BALL_THRESHOLD;
+        t.translation.x = court.x - DISPLAY_WIDTH / 2. - MARGIN;
+        t.translation.y = court.y - DISPLAY_HEIGHT / 2. - MARGIN;
+    }
+}
+
+/// Bins every ball into a coarse grid covering the court and paints the counts as a heatmap.
+pub fn update_minimap(
+    minimap : Res<MinimapImage>,
+    mut images : ResMut<Assets<Image>>,
+    balls : Query<&Transform, With<Ball>>,
+    ball_count : Res<BallCount>,
+    config : Res<GameConfig>,
+) {
+    if ball_count.0 < BALL_THRESHOLD {
+        return;
+    }
+
+    let half = config.court_half();
+
+    let mut cells = [0u32; CELLS_X * CELLS_Y];
+    for b in balls.iter() {
+        let uv = (b.translation.truncate() + half) / (half * 2.);
+        if uv.x < 0. || uv.x >= 1. || uv.y < 0. || uv.y >= 1. {
+            continue;
+        }
+        let x = (uv.x * CELLS_X as f32) as usize;
+        // image rows go top to bottom
+        let y = ((1. - uv.y) * CELLS_Y as f32) as usize;
+        cells[y.min(CELLS_Y - 1) * CELLS_X + x] += 1;
+    }
+
+    let image = match images.get_mut(&minimap.0) {
+        Some(image) => image,
+        None => return,
+    };
+
+    // log scale so a few dense clusters don't wash out the rest of the swarm
+    let max = (*cells.iter().max().unwrap_or(&0) as f32).ln_1p().max(1.);
+    for (pixel, count) in image.data.chunks_exact_mut(4).zip(cells.iter()) {
+        let heat = (*count as f32).ln_1p() / max;
+        pixel[0] = ((heat * 2.).min(1.) * 255.) as u8;
+        pixel[1] = ((heat * 2. - 1.).max(0.) * 255.) as u8;
+        pixel[2] = 0;
+        pixel[3] = if *count == 0 { 96 } else { 224 };
+    }
+}
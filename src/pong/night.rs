@@ -0,0 +1,122 @@
+use bevy::{asset::prelude::*, ecs::prelude::*, math::{Vec2, Vec3}, render::{color::Color, render_resource::{Extent3d, TextureDimension, TextureFormat}, texture::Image, view::Visibility}, sprite, transform::prelude::*};
+
+use super::{Ball, BallSprite, MatchEntity, config::GameConfig};
+
+/// Above the balls and paddles, below the fog, scores and HUD.
+const DARKNESS_Z: f32 = 0.5;
+const DARKNESS_ALPHA: f32 = 0.85;
+/// Above the darkness, so the light shows through it.
+const GLOW_Z: f32 = 0.6;
+/// Diameter of the lit area around each ball.
+const GLOW_SIZE: f32 = 240.;
+/// Resolution of the generated glow texture.
+const GLOW_PIXELS: u32 = 64;
+
+/// A radial falloff generated at startup, drawn around each ball in night mode.
+pub struct GlowTexture(Handle<Image>);
+
+pub fn create_glow_texture(
+    mut commands : Commands,
+    mut images : ResMut<Assets<Image>>,
+) {
+    let centre = (GLOW_PIXELS as f32 - 1.) / 2.;
+    let mut data = Vec::with_capacity((GLOW_PIXELS * GLOW_PIXELS * 4) as usize);
+    for y in 0..GLOW_PIXELS {
+        for x in 0..GLOW_PIXELS {
+            let distance = Vec2::new(x as f32 - centre, y as f32 - centre).length() / centre;
+            // squared falloff reads closer to a point light than a linear one
+            let light = (1. - distance).max(0.).powi(2);
+            data.extend_from_slice(&[255, 240, 200, (light * 255.) as u8]);
+        }
+    }
+    let image = images.add(Image::new(
+        Extent3d { width: GLOW_PIXELS, height: GLOW_PIXELS, depth_or_array_layers: 1 },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+    ));
+    commands.insert_resource(GlowTexture(image));
+}
+
+/// Dims the whole court while `GameConfig::night_mode` is on.
+#[derive(Component)]
+pub struct Darkness;
+
+pub fn spawn_darkness(
+    mut commands : Commands,
+    sprite : Res<BallSprite>,
+    config : Res<GameConfig>,
+) {
+    let size = config.court_half() * 2.;
+    let mut darkness = sprite.tinted(Color::rgba(0., 0., 0., DARKNESS_ALPHA), size, Vec3::new(0., 0., DARKNESS_Z));
+    darkness.visibility = Visibility { is_visible: config.night_mode };
+    commands.spawn_bundle(darkness).insert(Darkness).insert(MatchEntity);
+}
+
+pub fn update_darkness(
+    mut darkness : Query<(&mut sprite::Sprite, &mut Visibility), With<Darkness>>,
+    config : Res<GameConfig>,
+) {
+    if !config.is_changed() {
+        return;
+    }
+    for (mut sprite, mut visibility) in darkness.iter_mut() {
+        sprite.custom_size = Some(config.court_half() * 2.);
+        visibility.is_visible = config.night_mode;
+    }
+}
+
+/// Follows `.0` around, lighting up the court near it. Kept as its own entity rather than a child
+/// so despawning the ball doesn't need to be recursive everywhere balls go away.
+#[derive(Component)]
+pub struct Glow(Entity);
+
+/// Marks a ball that already has a `Glow` following it.
+#[derive(Component)]
+pub struct Glowing;
+
+pub fn attach_glows(
+    mut commands : Commands,
+    balls : Query<(Entity, &Transform), (With<Ball>, Without<Glowing>)>,
+    texture : Res<GlowTexture>,
+    config : Res<GameConfig>,
+) {
+    if !config.night_mode {
+        return;
+    }
+    for (e, t) in balls.iter() {
+        commands.spawn_bundle(sprite::SpriteBundle {
+            sprite: sprite::Sprite {
+                custom_size: Some(Vec2::splat(GLOW_SIZE)),
+                ..Default::default()
+            },
+            texture: texture.0.clone(),
+            transform: Transform::from_xyz(t.translation.x, t.translation.y, GLOW_Z),
+            ..Default::default()
+        })
+            .insert(Glow(e));
+        commands.entity(e).insert(Glowing);
+    }
+}
+
+/// Moves each glow onto its ball, despawning it once the ball is gone or night mode is turned off.
+pub fn follow_balls(
+    mut commands : Commands,
+    mut glows : Query<(Entity, &Glow, &mut Transform)>,
+    balls : Query<&Transform, (With<Ball>, Without<Glow>)>,
+    config : Res<GameConfig>,
+) {
+    for (e, glow, mut t) in glows.iter_mut() {
+        match balls.get(glow.0) {
+            Ok(ball) if config.night_mode => {
+                t.translation.x = ball.translation.x;
+                t.translation.y = ball.translation.y;
+            }
+            Ok(_) => {
+                commands.entity(glow.0).remove::<Glowing>();
+                commands.entity(e).despawn();
+            }
+            Err(_) => commands.entity(e).despawn(),
+        }
+    }
+}
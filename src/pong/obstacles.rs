@@ -0,0 +1,110 @@
+use bevy::{ecs::prelude::*, math::Vec2, render::color::Color, transform::prelude::*};
+use serde::{Deserialize, Serialize};
+
+use super::{Ball, BallSize, BallSprite, MatchEntity, Velocity, collision::{self, Collider}, config::GameConfig, profiler::SystemTimings};
+
+const OBSTACLE_Z: f32 = 0.;
+
+/// A box on the court from `config.ron`, patrolling along `path`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ObstacleConfig {
+    pub width: f32,
+    pub height: f32,
+    /// Points it moves between in order, looping back to the first. A single point stays put.
+    pub path: Vec<(f32, f32)>,
+    /// Pixels per second.
+    #[serde(default)]
+    pub speed: f32,
+}
+
+#[derive(Component)]
+pub struct Obstacle;
+
+#[derive(Component)]
+pub struct Patrol {
+    path: Vec<Vec2>,
+    next: usize,
+    speed: f32,
+}
+
+/// Spawns the obstacles for a match, and respawns them when the config changes mid-match.
+pub fn sync_obstacles(
+    mut commands : Commands,
+    obstacles : Query<Entity, With<Obstacle>>,
+    sprite : Res<BallSprite>,
+    config : Res<GameConfig>,
+) {
+    // on entering the match there is nothing to replace yet and the config counts as unchanged
+    if !config.is_changed() && obstacles.iter().next().is_some() {
+        return;
+    }
+    for e in obstacles.iter() {
+        commands.entity(e).despawn();
+    }
+
+    for obstacle in config.obstacles.iter() {
+        let path: Vec<Vec2> = obstacle.path.iter().map(|(x, y)| Vec2::new(*x, *y)).collect();
+        let start = path.first().copied().unwrap_or_default();
+        let size = Vec2::new(obstacle.width, obstacle.height);
+
+        commands.spawn_bundle(sprite.tinted(Color::rgb(0.5, 0.5, 0.6), size, start.extend(OBSTACLE_Z)))
+            .insert(Obstacle)
+            .insert(Collider(size / 2.))
+            .insert(Velocity(Vec2::ZERO))
+            .insert(Patrol { path, next: 0, speed: config.per_tick(obstacle.speed) })
+            .insert(MatchEntity);
+    }
+}
+
+/// Points each obstacle's velocity at its next waypoint; `update_velocity` does the moving.
+pub fn patrol_obstacles(
+    mut obstacles : Query<(&mut Patrol, &mut Velocity, &Transform), With<Obstacle>>,
+) {
+    for (mut patrol, mut v, t) in obstacles.iter_mut() {
+        if patrol.path.len() < 2 || patrol.speed <= 0. {
+            v.0 = Vec2::ZERO;
+            continue;
+        }
+
+        let delta = patrol.path[patrol.next] - t.translation.truncate();
+        if delta.length() <= patrol.speed {
+            // land exactly on the waypoint this tick, then head for the next one
+            v.0 = delta;
+            patrol.next = (patrol.next + 1) % patrol.path.len();
+        } else {
+            v.0 = delta.normalize() * patrol.speed;
+        }
+    }
+}
+
+/// Bounces balls off obstacles, reflecting them in the obstacle's frame so a moving obstacle carries them along.
+pub fn bounce_off_obstacles(
+    obstacles : Query<(&Transform, &Collider, &Velocity), (With<Obstacle>, Without<Ball>)>,
+    mut balls : Query<(&mut Velocity, &Transform, &BallSize), With<Ball>>,
+    mut timings : ResMut<SystemTimings>,
+    config : Res<GameConfig>,
+) {
+    let _timed = timings.time("bounce_off_obstacles");
+    let obstacles: Vec<(Vec2, Vec2, Vec2)> = obstacles.iter()
+        .map(|(t, collider, v)| (t.translation.truncate(), collider.0, v.0))
+        .collect();
+    if obstacles.is_empty() {
+        return;
+    }
+
+    for (mut v, t, size) in balls.iter_mut() {
+        let position = t.translation.truncate();
+        let ball_half = Vec2::splat(size.0 / 2.);
+        for (center, half, velocity) in obstacles.iter() {
+            if !collision::overlaps(position, ball_half, *center, *half) {
+                continue;
+            }
+            let normal = collision::contact_normal(position, ball_half, *center, *half);
+            let relative = v.0 - *velocity;
+            // only turn around if still heading into the obstacle
+            if relative.dot(normal) < 0. {
+                v.0 = config.limit_ball_velocity(collision::reflect(relative, normal) + *velocity);
+            }
+        }
+    }
+}
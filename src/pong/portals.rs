@@ -0,0 +1,125 @@
+use bevy::{core::prelude::*, ecs::prelude::*, math::{Quat, Vec2}, render::color::Color, transform::prelude::*};
+use serde::{Deserialize, Serialize};
+
+use super::{Ball, BallSprite, MatchEntity, Velocity, config::GameConfig, profiler::SystemTimings};
+
+const PORTAL_RADIUS: f32 = 20.;
+const PORTAL_Z: f32 = -0.5;
+/// Seconds a ball ignores portals after coming out of one, so it can't bounce straight back in.
+const COOLDOWN: f32 = 0.5;
+/// How fast the swirl turns, in radians per second; each ring turns the other way.
+const SWIRL_SPEED: f32 = 3.;
+const RINGS: usize = 3;
+
+/// Two linked portals from `config.ron`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PortalPairConfig {
+    pub a: (f32, f32),
+    pub b: (f32, f32),
+    /// Degrees a ball's direction is turned going from `a` to `b`, and turned back going from `b` to `a`.
+    #[serde(default)]
+    pub rotation: f32,
+}
+
+#[derive(Component)]
+pub struct Portal {
+    exit: Vec2,
+    /// Turns the direction of a ball going through, as the unit vector `(cos, sin)`.
+    turn: Vec2,
+}
+
+/// One spinning square of a portal's swirl.
+#[derive(Component)]
+pub struct PortalRing {
+    speed: f32,
+}
+
+/// Physics ticks left before a ball can go through a portal again.
+#[derive(Component, Default)]
+pub struct PortalCooldown(pub u32);
+
+/// Spawns the portals for a match, and respawns them when the config changes mid-match.
+pub fn sync_portals(
+    mut commands : Commands,
+    portals : Query<Entity, Or<(With<Portal>, With<PortalRing>)>>,
+    sprite : Res<BallSprite>,
+    config : Res<GameConfig>,
+) {
+    // on entering the match there is nothing to replace yet and the config counts as unchanged
+    if !config.is_changed() && portals.iter().next().is_some() {
+        return;
+    }
+    for e in portals.iter() {
+        commands.entity(e).despawn();
+    }
+
+    for pair in config.portals.iter() {
+        let a = Vec2::new(pair.a.0, pair.a.1);
+        let b = Vec2::new(pair.b.0, pair.b.1);
+        let angle = pair.rotation.to_radians();
+        let there = Vec2::new(angle.cos(), angle.sin());
+        let back = Vec2::new(there.x, -there.y);
+
+        for (position, exit, turn, color) in [
+            (a, b, there, Color::rgb(0.2, 0.5, 1.)),
+            (b, a, back, Color::rgb(1., 0.5, 0.1)),
+        ] {
+            commands.spawn()
+                .insert(Transform::from_xyz(position.x, position.y, PORTAL_Z))
+                .insert(Portal { exit, turn })
+                .insert(MatchEntity);
+
+            for ring in 0..RINGS {
+                let size = PORTAL_RADIUS * 2. * (1. - ring as f32 / RINGS as f32);
+                let mut ring_color = color;
+                ring_color.set_a(0.4 + 0.2 * ring as f32);
+                let bundle = sprite.tinted(ring_color, Vec2::splat(size), position.extend(PORTAL_Z + ring as f32 * 0.01));
+                commands.spawn_bundle(bundle)
+                    .insert(PortalRing { speed: if ring % 2 == 0 { SWIRL_SPEED } else { -SWIRL_SPEED } })
+                    .insert(MatchEntity);
+            }
+        }
+    }
+}
+
+/// Moves balls that entered a portal to the other end of the pair, keeping their speed.
+pub fn teleport_balls(
+    portals : Query<(&Portal, &Transform), Without<Ball>>,
+    mut balls : Query<(&mut Velocity, &mut Transform, &mut PortalCooldown), With<Ball>>,
+    mut timings : ResMut<SystemTimings>,
+    config : Res<GameConfig>,
+) {
+    let _timed = timings.time("teleport_balls");
+    let portals: Vec<(Vec2, &Portal)> = portals.iter()
+        .map(|(portal, t)| (t.translation.truncate(), portal))
+        .collect();
+    if portals.is_empty() {
+        return;
+    }
+
+    for (mut v, mut t, mut cooldown) in balls.iter_mut() {
+        if cooldown.0 > 0 {
+            cooldown.0 -= 1;
+            continue;
+        }
+
+        let position = t.translation.truncate();
+        let entered = portals.iter().find(|(center, _)| (position - *center).length_squared() < PORTAL_RADIUS * PORTAL_RADIUS);
+        if let Some((center, portal)) = entered {
+            let exit = portal.exit + (position - *center);
+            t.translation.x = exit.x;
+            t.translation.y = exit.y;
+            v.0 = Vec2::new(v.0.x * portal.turn.x - v.0.y * portal.turn.y, v.0.x * portal.turn.y + v.0.y * portal.turn.x);
+            cooldown.0 = config.ticks(COOLDOWN);
+        }
+    }
+}
+
+pub fn swirl_portals(
+    mut rings : Query<(&PortalRing, &mut Transform)>,
+    time : Res<Time>,
+) {
+    for (ring, mut t) in rings.iter_mut() {
+        t.rotation = Quat::from_rotation_z(ring.speed * time.seconds_since_startup() as f32);
+    }
+}
@@ -0,0 +1,158 @@
+use bevy::{core::prelude::*, ecs::prelude::*, input::prelude::*, math::{Vec2, Vec3}, render::{camera::OrthographicProjection, color::Color}, sprite, transform::prelude::*};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use super::{BallSprite, MatchEntity, Player, Who, config::GameConfig, scoreboard::ScoreboardCamera, sets::Sets};
+
+const SHOT_KEY: KeyCode = KeyCode::Space;
+/// Seconds of holding needed for a full charge.
+const CHARGE_TIME: f32 = 1.;
+/// Seconds after releasing during which a hit on the player paddle fires the shot.
+const RELEASE_WINDOW: f32 = 0.25;
+/// Seconds after releasing before charging can start again.
+const COOLDOWN: f32 = 3.;
+/// Extra ball speed at full charge, as a fraction of `ball_speed`.
+const MAX_BOOST: f32 = 0.75;
+const METER_WIDTH: f32 = 10.;
+const METER_HEIGHT: f32 = 80.;
+const METER_Z: f32 = 5.;
+/// Camera offset at full charge, in world units.
+const SHAKE_STRENGTH: f32 = 8.;
+/// How much shake wears off per second.
+const SHAKE_DECAY: f32 = 3.;
+
+/// Power shot state of the player paddle. Counted in physics ticks so replays re-simulate it.
+#[derive(Component, Clone, Default, Serialize, Deserialize)]
+pub struct PowerShot {
+    /// Read from the input every frame, so not worth saving.
+    #[serde(skip)]
+    pub held: bool,
+    charge: f32,
+    /// Charge that was released, and the ticks left to land it.
+    armed: Option<(f32, u32)>,
+    cooldown: u32,
+    fired: bool,
+}
+
+impl PowerShot {
+    /// Speed multiplier for a ball hit this tick, if a shot is armed.
+    pub fn boost(&self) -> Option<f32> {
+        self.armed.map(|(charge, _)| 1. + charge * MAX_BOOST)
+    }
+
+    /// Called when an armed shot landed; it is used up at the start of the next tick.
+    pub fn fire(&mut self) {
+        self.fired = true;
+    }
+}
+
+/// How much the camera is shaking, from 0 (still) to 1.
+#[derive(Default)]
+pub struct ScreenShake(f32);
+
+#[derive(Component)]
+pub struct PowerMeter;
+
+pub fn power_shot_input(
+    input : Res<Input<KeyCode>>,
+    mut shots : Query<&mut PowerShot, With<Player>>,
+) {
+    for mut shot in shots.iter_mut() {
+        shot.held = input.pressed(SHOT_KEY);
+    }
+}
+
+pub fn charge_power_shot(
+    mut shots : Query<&mut PowerShot>,
+    mut shake : ResMut<ScreenShake>,
+    config : Res<GameConfig>,
+) {
+    for mut shot in shots.iter_mut() {
+        if shot.fired {
+            shot.fired = false;
+            if let Some((charge, _)) = shot.armed.take() {
+                shake.0 = shake.0.max(charge);
+            }
+        }
+
+        if let Some((charge, ticks)) = shot.armed {
+            shot.armed = if ticks > 1 { Some((charge, ticks - 1)) } else { None };
+        }
+
+        if shot.cooldown > 0 {
+            shot.cooldown -= 1;
+            continue;
+        }
+
+        if shot.held {
+            shot.charge = (shot.charge + config.per_tick(1. / CHARGE_TIME)).min(1.);
+        } else if shot.charge > 0. {
+            shot.armed = Some((shot.charge, config.ticks(RELEASE_WINDOW)));
+            shot.charge = 0.;
+            shot.cooldown = config.ticks(COOLDOWN);
+        }
+    }
+}
+
+pub fn spawn_power_meter(
+    mut commands: Commands,
+    sprite: Res<BallSprite>,
+) {
+    // placed by update_power_meter
+    commands.spawn_bundle(sprite.bundle(Vec2::new(METER_WIDTH, 0.), Vec3::ZERO))
+        .insert(PowerMeter)
+        .insert(MatchEntity);
+}
+
+/// Draws the charge as a bar growing up from beside the score on the player's side.
+pub fn update_power_meter(
+    shots : Query<&PowerShot, With<Player>>,
+    mut meter : Query<(&mut sprite::Sprite, &mut Transform), With<PowerMeter>>,
+    sets : Res<Sets>,
+    config : Res<GameConfig>,
+) {
+    let shot = match shots.iter().next() {
+        Some(shot) => shot,
+        None => return,
+    };
+
+    let court = config.court_half();
+    let (fill, color) = if shot.armed.is_some() {
+        (1., Color::ORANGE)
+    } else if shot.cooldown > 0 {
+        (1. - shot.cooldown as f32 / config.ticks(COOLDOWN) as f32, Color::GRAY)
+    } else if shot.charge >= 1. {
+        (1., Color::YELLOW)
+    } else {
+        (shot.charge, Color::WHITE)
+    };
+
+    for (mut sprite, mut t) in meter.iter_mut() {
+        let height = METER_HEIGHT * fill;
+        sprite.custom_size = Some(Vec2::new(METER_WIDTH, height));
+        sprite.color = color;
+        t.translation.x = sets.side_of(Who::PLAYER) * court.x / 2. - 2. * METER_WIDTH;
+        t.translation.y = -court.y + height / 2.;
+        t.translation.z = METER_Z;
+    }
+}
+
+/// Jitters the camera while there is shake left. Uses its own rng so it can't affect the simulation.
+pub fn shake_camera(
+    mut shake : ResMut<ScreenShake>,
+    mut cameras : Query<&mut Transform, (With<OrthographicProjection>, Without<ScoreboardCamera>)>,
+    time : Res<Time>,
+) {
+    if shake.0 <= 0. {
+        return;
+    }
+    shake.0 = (shake.0 - SHAKE_DECAY * time.delta_seconds()).max(0.);
+
+    let mut rng = rand::thread_rng();
+    // squared so small shakes stay subtle; reaches 0 on the last frame, recentring the camera
+    let offset = shake.0 * shake.0 * SHAKE_STRENGTH;
+    for mut t in cameras.iter_mut() {
+        t.translation.x = rng.gen_range(-1.0..=1.0) * offset;
+        t.translation.y = rng.gen_range(-1.0..=1.0) * offset;
+    }
+}
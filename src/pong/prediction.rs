@@ -0,0 +1,39 @@
+use bevy::math::Vec2;
+
+/// Most wall bounces followed before giving up on a (nearly) vertical path.
+pub const MAX_BOUNCES: usize = 4;
+
+/// Extrapolates a ball at `position` moving with `velocity` until it reaches `target_x`, bouncing
+/// off the top and bottom walls at `±wall_y`. Returns the path as points, starting at
+/// `position` and ending at `target_x` if it is reached within `MAX_BOUNCES` bounces.
+///
+/// Only the court walls are accounted for; hazards, portals, obstacles and bricks are not.
+pub fn predict_path(position: Vec2, velocity: Vec2, wall_y: f32, target_x: f32) -> Vec<Vec2> {
+    let mut points = vec![position];
+    if velocity.x == 0. || (target_x - position.x) * velocity.x < 0. {
+        return points;
+    }
+
+    let mut p = position;
+    let mut v = velocity;
+    for _ in 0..=MAX_BOUNCES {
+        let to_target = (target_x - p.x) / v.x;
+        // a ball already past a wall is turned around straight away, like ball_bounce does
+        let to_wall = if v.y > 0. {
+            ((wall_y - p.y) / v.y).max(0.)
+        } else if v.y < 0. {
+            ((-wall_y - p.y) / v.y).max(0.)
+        } else {
+            f32::MAX
+        };
+
+        if to_target <= to_wall {
+            points.push(p + v * to_target);
+            break;
+        }
+        p += v * to_wall;
+        v.y = -v.y;
+        points.push(p);
+    }
+    points
+}
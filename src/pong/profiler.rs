@@ -0,0 +1,72 @@
+use std::time::{Duration, Instant};
+
+use bevy::ecs::prelude::*;
+
+/// Weight of the newest frame in the running averages.
+const SMOOTHING: f64 = 0.1;
+
+/// How long the hot systems take per frame. Fixed tick systems can run several times a frame;
+/// their runs are added up.
+#[derive(Default)]
+pub struct SystemTimings {
+    /// Time spent this frame so far.
+    frame: Vec<(&'static str, Duration)>,
+    /// Smoothed milliseconds per frame.
+    averages: Vec<(&'static str, f64)>,
+    #[cfg(feature = "trace")]
+    pub capture: super::trace::TraceCapture,
+}
+
+impl SystemTimings {
+    /// Times the rest of the calling scope as a run of `name`.
+    pub fn time(&mut self, name: &'static str) -> Timed<'_> {
+        Timed { timings: self, name, start: Instant::now() }
+    }
+
+    fn add(&mut self, name: &'static str, start: Instant, elapsed: Duration) {
+        #[cfg(feature = "trace")]
+        self.capture.record(name, start, elapsed);
+        #[cfg(not(feature = "trace"))]
+        let _ = start;
+        match self.frame.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, total)) => *total += elapsed,
+            None => self.frame.push((name, elapsed)),
+        }
+    }
+
+    pub fn averages(&self) -> impl Iterator<Item = &(&'static str, f64)> {
+        self.averages.iter()
+    }
+}
+
+pub struct Timed<'a> {
+    timings: &'a mut SystemTimings,
+    name: &'static str,
+    start: Instant,
+}
+
+impl Drop for Timed<'_> {
+    fn drop(&mut self) {
+        self.timings.add(self.name, self.start, self.start.elapsed());
+    }
+}
+
+/// Folds this frame's timings into the averages. Systems that didn't run this frame count as 0.
+pub fn end_frame(
+    mut timings : ResMut<SystemTimings>,
+) {
+    let timings = &mut *timings;
+    for (name, _) in timings.frame.iter() {
+        if !timings.averages.iter().any(|(n, _)| n == name) {
+            timings.averages.push((name, 0.));
+        }
+    }
+    for (name, average) in timings.averages.iter_mut() {
+        let ms = timings.frame.iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, elapsed)| elapsed.as_secs_f64() * 1000.)
+            .unwrap_or(0.);
+        *average += (ms - *average) * SMOOTHING;
+    }
+    timings.frame.clear();
+}
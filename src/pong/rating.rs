@@ -0,0 +1,115 @@
+use std::{fs, io};
+
+use bevy::{ecs::prelude::*, log::{info, warn}};
+use serde::{Deserialize, Serialize};
+
+use super::{BotDifficulty, Who, config::GameConfig, screens::Winner};
+
+pub const PROFILE_PATH: &str = "profile.ron";
+const STARTING_RATING: f32 = 1200.;
+/// Most a single match can move a rating by.
+const K_FACTOR: f32 = 32.;
+
+/// The local player, saved to `profile.ron`.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Profile {
+    pub name: String,
+    pub ratings: Ratings,
+    /// Whether the handheld preset has been taken or turned down, so it is only offered once.
+    pub handheld_prompt_answered: bool,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self { name: "Player".to_string(), ratings: Ratings::default(), handheld_prompt_answered: false }
+    }
+}
+
+/// The player's rating against each bot difficulty, kept apart so beating the easy bot doesn't
+/// inflate the rating against the hard one.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Ratings {
+    pub easy: f32,
+    pub normal: f32,
+    pub hard: f32,
+}
+
+impl Default for Ratings {
+    fn default() -> Self {
+        Self { easy: STARTING_RATING, normal: STARTING_RATING, hard: STARTING_RATING }
+    }
+}
+
+impl Ratings {
+    pub fn get_mut(&mut self, difficulty: BotDifficulty) -> &mut f32 {
+        match difficulty {
+            BotDifficulty::Easy => &mut self.easy,
+            BotDifficulty::Normal => &mut self.normal,
+            BotDifficulty::Hard => &mut self.hard,
+        }
+    }
+}
+
+/// How a match moved the player's rating.
+pub struct RatingChange {
+    pub difficulty: BotDifficulty,
+    pub rating: f32,
+    pub delta: f32,
+}
+
+/// The change from the match that just ended, for the game over screen.
+#[derive(Default)]
+pub struct LastRatingChange(pub Option<RatingChange>);
+
+impl BotDifficulty {
+    /// The bot never changes, so it is held at a fixed rating the player's rating is measured against.
+    fn rating(self) -> f32 {
+        match self {
+            BotDifficulty::Easy => 1000.,
+            BotDifficulty::Normal => 1200.,
+            BotDifficulty::Hard => 1400.,
+        }
+    }
+}
+
+/// Standard Elo: the change a match between `rating` and `opponent` makes to `rating`.
+pub fn elo_delta(rating: f32, opponent: f32, won: bool) -> f32 {
+    let expected = 1. / (1. + 10f32.powf((opponent - rating) / 400.));
+    let actual = if won { 1. } else { 0. };
+    K_FACTOR * (actual - expected)
+}
+
+pub fn load() -> Result<Profile, String> {
+    match fs::read_to_string(PROFILE_PATH) {
+        Ok(data) => ron::from_str(&data).map_err(|e| e.to_string()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Profile::default()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+pub fn save(profile: &Profile) -> Result<(), String> {
+    let data = ron::ser::to_string_pretty(profile, Default::default()).map_err(|e| e.to_string())?;
+    fs::write(PROFILE_PATH, data).map_err(|e| e.to_string())
+}
+
+/// Rates the match that just ended against the difficulty nearest the bot's speed, and saves the profile.
+pub fn rate_match(
+    mut last : ResMut<LastRatingChange>,
+    mut profile : ResMut<Profile>,
+    winner : Res<Winner>,
+    config : Res<GameConfig>,
+) {
+    let difficulty = BotDifficulty::nearest(config.bot_paddle_speed);
+    let rating = profile.ratings.get_mut(difficulty);
+    let delta = elo_delta(*rating, difficulty.rating(), winner.0 == Who::PLAYER);
+    *rating += delta;
+    let rating = *rating;
+
+    match save(&profile) {
+        Ok(()) => info!("rating against {:?} bot is now {:.0}", difficulty, rating),
+        Err(e) => warn!("could not save {}: {}", PROFILE_PATH, e),
+    }
+    last.0 = Some(RatingChange { difficulty, rating, delta });
+}
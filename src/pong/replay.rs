@@ -0,0 +1,371 @@
+//! Replay recording and the replay file format.
+//!
+//! A replay is a RON document with two parts:
+//!
+//! - `header`
+//!   - `version`: format version, see [`REPLAY_VERSION`].
+//!   - `config_hash`: FNV-1a hash of the RON-serialized `SimConfig` of the config the match was
+//!     played with. The simulation depends on every one of those values, so a replay only plays
+//!     back correctly with a config that hashes the same; graphics, text and other settings that
+//!     don't change the simulation are left out.
+//!   - `seed`: the seed `GameRng` was reset to when the match started.
+//!   - `length`: how many physics ticks the match lasted.
+//!   - `first_server`: who served first, `PLAYER` or `OPPONENT`. Rematches swap it.
+//! - `inputs`: the player paddle's input as `(tick, input, charging)` changes, ordered by tick.
+//!   `tick` is the physics tick (counted from 0 at the start of the match) the input takes effect
+//!   on, `input` is `-1` (down), `0` (still) or `1` (up) and `charging` whether the power shot
+//!   button is held. An input holds until the next entry.
+//! - `score_ticks`: the ticks a point was scored on, in order.
+//!
+//! Version 1 had no `charging`; those files load with it always `false`. Versions 1 and 2 had no
+//! `length` or `score_ticks`; those load as `0` and empty. Versions 1 to 3 had no `first_server`;
+//! those matches were always served first by the player.
+//!
+//! The bot, serves and ball physics are all re-simulated from the seed and the inputs.
+//!
+//! Files written by older versions of the game are upgraded in [`migrate`] when loaded, so the
+//! layout of a released version must never change; bump [`REPLAY_VERSION`] and add a migration
+//! arm instead.
+
+use std::{fs, iter, path::{Path, PathBuf}, time::{SystemTime, UNIX_EPOCH}};
+
+use bevy::{ecs::prelude::*, log::{info, warn}};
+use rand::SeedableRng;
+use rand_pcg::Pcg32;
+use serde::{Deserialize, Serialize};
+
+use super::{GameRng, MatchTick, Player, Score, Velocity, Who, ball_kind::BallMix, collision::PaddleShape, config::{GameConfig, Handicap}, event_log::{EventLog, GameEvent}, hazards::HazardConfig, obstacles::ObstacleConfig, portals::PortalPairConfig, power_shot::PowerShot, serve::FirstServer, sets::Sets};
+
+pub const REPLAY_VERSION: u32 = 4;
+/// Where finished matches are saved to.
+pub const REPLAY_DIR: &str = "replays";
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ReplayHeader {
+    pub version: u32,
+    pub config_hash: u64,
+    pub seed: u64,
+    #[serde(default)]
+    pub length: u32,
+    #[serde(default)]
+    pub first_server: Who,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct InputChange {
+    pub tick: u32,
+    pub input: i8,
+    #[serde(default)]
+    pub charging: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Replay {
+    pub header: ReplayHeader,
+    pub inputs: Vec<InputChange>,
+    #[serde(default)]
+    pub score_ticks: Vec<u32>,
+}
+
+impl Replay {
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let data = ron::ser::to_string_pretty(self, Default::default()).map_err(|e| e.to_string())?;
+        fs::write(path, data).map_err(|e| e.to_string())
+    }
+
+    pub fn load(path: &str) -> Result<Replay, String> {
+        let data = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let probe: VersionProbe = ron::from_str(&data).map_err(|e| e.to_string())?;
+        migrate(probe.header.version, &data)
+    }
+
+    /// The player input in effect on `tick`.
+    pub fn input_at(&self, tick: u32) -> InputChange {
+        match self.inputs.binary_search_by_key(&tick, |change| change.tick) {
+            Ok(i) => self.inputs[i],
+            Err(0) => InputChange { tick, input: 0, charging: false },
+            Err(i) => self.inputs[i - 1],
+        }
+    }
+}
+
+/// Just enough of any replay version to read its version number.
+#[derive(Deserialize)]
+#[serde(rename = "Replay")]
+struct VersionProbe {
+    header: HeaderProbe,
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "ReplayHeader")]
+struct HeaderProbe {
+    version: u32,
+}
+
+/// Parses replay `data` written with format `version` into the current layout.
+fn migrate(version: u32, data: &str) -> Result<Replay, String> {
+    match version {
+        REPLAY_VERSION => ron::from_str(data).map_err(|e| e.to_string()),
+        // later versions only added fields that have defaults
+        1 | 2 | 3 => {
+            let mut replay: Replay = ron::from_str(data).map_err(|e| e.to_string())?;
+            replay.header.version = REPLAY_VERSION;
+            Ok(replay)
+        }
+        // older versions get an arm here parsing their own layout and converting it to `Replay`
+        v if v > REPLAY_VERSION => Err(format!("replay version {} is newer than this game supports ({})", v, REPLAY_VERSION)),
+        v => Err(format!("unknown replay version {}", v)),
+    }
+}
+
+/// Stable 64-bit FNV-1a, used instead of `DefaultHasher` whose output may change between Rust releases.
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, b| (hash ^ *b as u64).wrapping_mul(0x100000001b3))
+}
+
+/// The parts of a [`GameConfig`] the simulation depends on.
+#[derive(Serialize)]
+struct SimConfig<'a> {
+    paddle_offset: f32,
+    paddle_width: f32,
+    paddle_height: f32,
+    paddle_speed: f32,
+    bot_paddle_speed: f32,
+    ball_size: f32,
+    ball_speed: f32,
+    ball_launch_time: f32,
+    min_ball_speed_x: f32,
+    max_ball_speed: f32,
+    tick_rate: u32,
+    /// `balls_amount` as limited by the graphics preset.
+    ball_cap: i64,
+    ball_mix: &'a BallMix,
+    score_limit: i32,
+    win_by_two: bool,
+    serve_every: u32,
+    sets_to_win: u32,
+    switch_sides: bool,
+    court_width: f32,
+    court_height: f32,
+    goal_height: f32,
+    player_paddle_shape: &'a PaddleShape,
+    bot_paddle_shape: &'a PaddleShape,
+    stamina: bool,
+    breakout: bool,
+    combo_returns: u32,
+    hazards: &'a [HazardConfig],
+    portals: &'a [PortalPairConfig],
+    obstacles: &'a [ObstacleConfig],
+    player_handicap: &'a Handicap,
+    bot_handicap: &'a Handicap,
+}
+
+impl<'a> SimConfig<'a> {
+    fn of(config: &'a GameConfig) -> Self {
+        Self {
+            paddle_offset: config.paddle_offset,
+            paddle_width: config.paddle_width,
+            paddle_height: config.paddle_height,
+            paddle_speed: config.paddle_speed,
+            bot_paddle_speed: config.bot_paddle_speed,
+            ball_size: config.ball_size,
+            ball_speed: config.ball_speed,
+            ball_launch_time: config.ball_launch_time,
+            min_ball_speed_x: config.min_ball_speed_x,
+            max_ball_speed: config.max_ball_speed,
+            tick_rate: config.tick_rate,
+            ball_cap: config.ball_cap(),
+            ball_mix: &config.ball_mix,
+            score_limit: config.score_limit,
+            win_by_two: config.win_by_two,
+            serve_every: config.serve_every,
+            sets_to_win: config.sets_to_win,
+            switch_sides: config.switch_sides,
+            court_width: config.court_width,
+            court_height: config.court_height,
+            goal_height: config.goal_height,
+            player_paddle_shape: &config.player_paddle_shape,
+            bot_paddle_shape: &config.bot_paddle_shape,
+            stamina: config.stamina,
+            breakout: config.breakout,
+            combo_returns: config.combo_returns,
+            hazards: &config.hazards,
+            portals: &config.portals,
+            obstacles: &config.obstacles,
+            player_handicap: &config.player_handicap,
+            bot_handicap: &config.bot_handicap,
+        }
+    }
+}
+
+pub fn config_hash(config: &GameConfig) -> u64 {
+    fnv1a(ron::to_string(&SimConfig::of(config)).unwrap_or_default().as_bytes())
+}
+
+/// Records the inputs of the match in progress.
+#[derive(Default)]
+pub struct ReplayRecorder {
+    replay: Option<Replay>,
+}
+
+impl ReplayRecorder {
+    /// Seed of the match being recorded.
+    pub fn seed(&self) -> Option<u64> {
+        self.replay.as_ref().map(|replay| replay.header.seed)
+    }
+
+    /// Stops recording without keeping anything, for a match that can no longer be re-simulated
+    /// from its seed, like one loaded from a save.
+    pub fn discard(&mut self) {
+        self.replay = None;
+    }
+}
+
+/// The replay of the most recently finished match.
+#[derive(Default)]
+pub struct LastReplay(pub Option<Replay>);
+
+pub fn start_recording(
+    mut recorder : ResMut<ReplayRecorder>,
+    mut rng : ResMut<GameRng>,
+    mut tick : ResMut<MatchTick>,
+    config : Res<GameConfig>,
+    first : Res<FirstServer>,
+) {
+    let seed = rand::random::<u64>();
+    rng.0 = Pcg32::seed_from_u64(seed);
+    tick.0 = 0;
+    recorder.replay = Some(Replay {
+        header: ReplayHeader {
+            version: REPLAY_VERSION,
+            config_hash: config_hash(&config),
+            seed,
+            length: 0,
+            first_server: first.0,
+        },
+        inputs: Vec::new(),
+        score_ticks: Vec::new(),
+    });
+}
+
+pub fn record_input(
+    mut recorder : ResMut<ReplayRecorder>,
+    players : Query<(&Velocity, Option<&PowerShot>), With<Player>>,
+    tick : Res<MatchTick>,
+) {
+    let replay = match recorder.replay.as_mut() {
+        Some(replay) => replay,
+        None => return,
+    };
+
+    for (v, shot) in players.iter() {
+        let input = if v.0.y > 0. { 1 } else if v.0.y < 0. { -1 } else { 0 };
+        let charging = shot.map(|shot| shot.held).unwrap_or(false);
+        let last = replay.inputs.last().map(|change| (change.input, change.charging)).unwrap_or((0, false));
+        if (input, charging) != last {
+            replay.inputs.push(InputChange { tick: tick.0, input, charging });
+        }
+    }
+}
+
+pub fn record_scores(
+    mut recorder : ResMut<ReplayRecorder>,
+    mut seen : Local<usize>,
+    log : Res<EventLog>,
+    tick : Res<MatchTick>,
+) {
+    let new = log.pushed_since(*seen).filter(|entry| matches!(entry.event, GameEvent::Score(_))).count();
+    *seen = log.pushed();
+    if let Some(replay) = recorder.replay.as_mut() {
+        replay.score_ticks.extend(iter::repeat(tick.0).take(new));
+    }
+}
+
+/// UTC `YYYY-MM-DD_HH-MM-SS` for `secs` since the Unix epoch, safe to use in file names.
+pub fn timestamp(secs: u64) -> String {
+    let days = (secs / 86400) as i64;
+    let rem = secs % 86400;
+
+    // days since 1970-01-01 to a civil date, http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}-{:02}-{:02}_{:02}-{:02}-{:02}", year, month, day, rem / 3600, rem / 60 % 60, rem % 60)
+}
+
+/// The replays in `dir`, oldest first. File names start with their timestamp, so sorting them
+/// by name sorts them by age.
+fn replay_files(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let mut files: Vec<_> = fs::read_dir(dir).map_err(|e| e.to_string())?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().map_or(false, |ext| ext == "ron"))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// The most recently saved replay in `REPLAY_DIR`.
+pub fn load_newest() -> Result<Replay, String> {
+    let files = replay_files(Path::new(REPLAY_DIR))?;
+    let path = files.last().ok_or_else(|| format!("no replays in {}", REPLAY_DIR))?;
+    Replay::load(&path.to_string_lossy())
+}
+
+/// Deletes the oldest replays in `dir` until at most `keep` are left.
+fn prune_replays(dir: &Path, keep: usize) -> Result<(), String> {
+    let files = replay_files(dir)?;
+    if files.len() <= keep {
+        return Ok(());
+    }
+    for path in &files[..files.len() - keep] {
+        fs::remove_file(path).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+fn save_to_dir(replay: &Replay, mode: &str, player: i32, bot: i32, keep: u32) -> Result<String, String> {
+    fs::create_dir_all(REPLAY_DIR).map_err(|e| e.to_string())?;
+    let secs = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default();
+    let path = format!("{}/{}_{}_{}-{}.ron", REPLAY_DIR, timestamp(secs), mode, player, bot);
+    replay.save(&path)?;
+    if keep > 0 {
+        prune_replays(Path::new(REPLAY_DIR), keep as usize)?;
+    }
+    Ok(path)
+}
+
+/// Saves a match that was played to the end to `REPLAY_DIR`, named after when it ended, the mode
+/// and the score. A match left before then is only kept as the last replay.
+pub fn finish_recording(
+    mut recorder : ResMut<ReplayRecorder>,
+    mut last : ResMut<LastReplay>,
+    scores : Query<&Score>,
+    tick : Res<MatchTick>,
+    sets : Res<Sets>,
+    config : Res<GameConfig>,
+) {
+    let mut replay = match recorder.replay.take() {
+        Some(replay) => replay,
+        None => return,
+    };
+    replay.header.length = tick.0;
+
+    if tick.0 > 0 && sets.is_over(&config) {
+        // counters are keyed by the side balls go out on, so the player's points are under OPPONENT
+        let points = |side: Who| scores.iter().find(|s| s.0 == side).map(|s| s.1).unwrap_or_default();
+        let mode = if config.breakout { "breakout" } else { "classic" };
+        match save_to_dir(&replay, mode, points(Who::OPPONENT), points(Who::PLAYER), config.max_replays) {
+            Ok(path) => info!("saved replay to {}", path),
+            Err(e) => warn!("could not save replay: {}", e),
+        }
+    }
+
+    last.0 = Some(replay);
+}
@@ -0,0 +1,240 @@
+use bevy::{ecs::prelude::*, input::prelude::*, log::warn, math::{Vec2, Vec3}, render::color::Color, sprite, text::prelude::*, transform::prelude::*};
+use rand::SeedableRng;
+use rand_pcg::Pcg32;
+
+use super::{BallSprite, GameRng, GameState, MatchEntity, MatchTick, Player, UiFont, Velocity, config::GameConfig, power_shot::PowerShot, replay::{self, LastReplay, Replay}, serve::Serve, sim_clock::{SimClock, SimulationSpeed}, transition::Transition};
+
+const PAUSE_KEY: KeyCode = KeyCode::Space;
+const PREVIOUS_KEY: KeyCode = KeyCode::Left;
+const NEXT_KEY: KeyCode = KeyCode::Right;
+const RESTART_KEY: KeyCode = KeyCode::Home;
+/// Seconds before a point that jumping to it lands on, so the point itself can be watched.
+const MARKER_LEAD: f32 = 2.;
+const TIMELINE_WIDTH: f32 = 800.;
+const TIMELINE_HEIGHT: f32 = 6.;
+const TIMELINE_MARGIN: f32 = 50.;
+const MARKER_WIDTH: f32 = 3.;
+const MARKER_HEIGHT: f32 = 16.;
+const TIMELINE_Z: f32 = 20.;
+
+/// The replay being watched in `GameState::Replay`.
+#[derive(Default)]
+pub struct ReplayViewer {
+    replay: Option<Replay>,
+    /// Tick to fast-forward to once playback has restarted from the beginning.
+    pending_seek: Option<u32>,
+}
+
+impl ReplayViewer {
+    pub fn watching(replay: Replay) -> Self {
+        Self { replay: Some(replay), pending_seek: None }
+    }
+}
+
+#[derive(Component)]
+pub struct TimelineFill;
+#[derive(Component)]
+pub struct ReplayStatus;
+
+/// Restarts playback from the first tick: going back in time means re-simulating from the start.
+pub fn restart_playback(
+    mut state : ResMut<State<GameState>>,
+) {
+    let _ = state.set(GameState::Replay);
+}
+
+/// Picks the replay to watch, resets the simulation to its seed and lays out the timeline.
+pub fn begin_playback(
+    mut commands : Commands,
+    mut viewer : ResMut<ReplayViewer>,
+    mut clock : ResMut<SimClock>,
+    mut rng : ResMut<GameRng>,
+    mut tick : ResMut<MatchTick>,
+    mut serve : ResMut<Serve>,
+    mut state : ResMut<State<GameState>>,
+    last : Res<LastReplay>,
+    sprite : Res<BallSprite>,
+    font : Res<UiFont>,
+    config : Res<GameConfig>,
+) {
+    if viewer.replay.is_none() {
+        let picked = match last.0.clone() {
+            Some(replay) => Ok(replay),
+            None => replay::load_newest(),
+        };
+        viewer.replay = match picked {
+            Ok(replay) => Some(replay),
+            Err(e) => {
+                warn!("no replay to watch: {}", e);
+                let _ = state.set(GameState::Menu);
+                return;
+            }
+        };
+    }
+    let replay = viewer.replay.as_ref().unwrap();
+    if replay.header.config_hash != replay::config_hash(&config) {
+        warn!("replay was recorded with a different config and will play back differently");
+    }
+
+    rng.0 = Pcg32::seed_from_u64(replay.header.seed);
+    tick.0 = 0;
+    *serve = Serve::first(replay.header.first_server);
+    clock.paused = false;
+    clock.fast_forward = viewer.pending_seek.take().unwrap_or(0);
+
+    let y = config.court_half().y - TIMELINE_MARGIN;
+    let bar = sprite.tinted(Color::rgba(1., 1., 1., 0.2), Vec2::new(TIMELINE_WIDTH, TIMELINE_HEIGHT), Vec3::new(0., y, TIMELINE_Z));
+    commands.spawn_bundle(bar).insert(MatchEntity);
+
+    let fill = sprite.bundle(Vec2::new(0., TIMELINE_HEIGHT), Vec3::new(-TIMELINE_WIDTH / 2., y, TIMELINE_Z + 0.1));
+    commands.spawn_bundle(fill).insert(TimelineFill).insert(MatchEntity);
+
+    if replay.header.length > 0 {
+        for score_tick in replay.score_ticks.iter() {
+            let x = (*score_tick as f32 / replay.header.length as f32 - 0.5) * TIMELINE_WIDTH;
+            let marker = sprite.tinted(Color::YELLOW, Vec2::new(MARKER_WIDTH, MARKER_HEIGHT), Vec3::new(x, y, TIMELINE_Z + 0.2));
+            commands.spawn_bundle(marker).insert(MatchEntity);
+        }
+    }
+
+    commands.spawn_bundle(Text2dBundle {
+        text: Text::with_section(
+            "", TextStyle {
+                font: font.0.clone(),
+                font_size: 30.0,
+                color: Color::WHITE,
+            }, TextAlignment {
+                vertical: VerticalAlign::Center,
+                horizontal: HorizontalAlign::Center,
+            }),
+        transform: Transform::from_xyz(0., y - 30., TIMELINE_Z),
+        ..Default::default()
+    })
+        .insert(ReplayStatus)
+        .insert(MatchEntity);
+}
+
+/// Feeds the recorded player input in on the tick it was recorded on, in place of the keyboard.
+pub fn replay_input(
+    viewer : Res<ReplayViewer>,
+    tick : Res<MatchTick>,
+    state : Res<State<GameState>>,
+    mut players : Query<(&mut Velocity, &mut PowerShot), With<Player>>,
+    config : Res<GameConfig>,
+) {
+    if *state.current() != GameState::Replay {
+        return;
+    }
+    let replay = match viewer.replay.as_ref() {
+        Some(replay) => replay,
+        None => return,
+    };
+
+    let input = replay.input_at(tick.0);
+    for (mut v, mut shot) in players.iter_mut() {
+        v.0.y = input.input as f32 * config.paddle_speed_of(true);
+        shot.held = input.charging;
+    }
+}
+
+pub fn viewer_keys(
+    mut input : ResMut<Input<KeyCode>>,
+    mut viewer : ResMut<ReplayViewer>,
+    mut clock : ResMut<SimClock>,
+    mut state : ResMut<State<GameState>>,
+    mut transition : ResMut<Transition>,
+    tick : Res<MatchTick>,
+    config : Res<GameConfig>,
+) {
+    let replay = match viewer.replay.as_ref() {
+        Some(replay) => replay,
+        None => return,
+    };
+    // where playback will be once any fast-forwarding is done
+    let position = tick.0 + clock.fast_forward;
+    let lead = config.ticks(MARKER_LEAD);
+
+    if input.just_pressed(PAUSE_KEY) {
+        clock.paused = !clock.paused;
+    }
+
+    let target = if input.just_pressed(RESTART_KEY) {
+        Some(0)
+    } else if input.just_pressed(PREVIOUS_KEY) {
+        // skip the point being watched, if within its lead
+        let current = position.saturating_sub(lead);
+        Some(replay.score_ticks.iter().rev()
+            .map(|t| t.saturating_sub(lead))
+            .find(|t| *t < current)
+            .unwrap_or(0))
+    } else if input.just_pressed(NEXT_KEY) {
+        replay.score_ticks.iter()
+            .map(|t| t.saturating_sub(lead))
+            .find(|t| *t > position)
+    } else {
+        None
+    };
+
+    match target {
+        Some(target) if target >= tick.0 => clock.fast_forward = target - tick.0,
+        Some(target) => {
+            viewer.pending_seek = Some(target);
+            let _ = state.set(GameState::ReplayLoading);
+        }
+        None => {}
+    }
+
+    if input.just_pressed(KeyCode::Escape) {
+        input.reset(KeyCode::Escape);
+        viewer.replay = None;
+        transition.fade_to(GameState::Menu);
+    }
+}
+
+/// Pauses at the end of the match and keeps the timeline and status line up to date.
+pub fn update_timeline(
+    viewer : Res<ReplayViewer>,
+    mut clock : ResMut<SimClock>,
+    tick : Res<MatchTick>,
+    speed : Res<SimulationSpeed>,
+    mut fill : Query<(&mut sprite::Sprite, &mut Transform), With<TimelineFill>>,
+    mut status : Query<&mut Text, With<ReplayStatus>>,
+) {
+    let length = match viewer.replay.as_ref() {
+        Some(replay) => replay.header.length,
+        None => return,
+    };
+
+    if length > 0 && tick.0 >= length && !clock.paused {
+        clock.paused = true;
+    }
+
+    if length > 0 {
+        let width = (tick.0 as f32 / length as f32).min(1.) * TIMELINE_WIDTH;
+        for (mut sprite, mut t) in fill.iter_mut() {
+            sprite.custom_size = Some(Vec2::new(width, TIMELINE_HEIGHT));
+            t.translation.x = (width - TIMELINE_WIDTH) / 2.;
+        }
+    }
+
+    let label = if clock.fast_forward > 0 {
+        "Seeking...".to_string()
+    } else if clock.paused {
+        "Paused".to_string()
+    } else {
+        format!("{}x", speed.0)
+    };
+    for mut text in status.iter_mut() {
+        if text.sections[0].value != label {
+            text.sections[0].value = label.clone();
+        }
+    }
+}
+
+/// Leaves the clock running normally for whatever comes after the replay.
+pub fn end_playback(
+    mut clock : ResMut<SimClock>,
+) {
+    clock.paused = false;
+    clock.fast_forward = 0;
+}
@@ -0,0 +1,73 @@
+//! Reproduction files for bug reports: the config, seed and player input of a match, written with
+//! `--record-input <file>` and played back with `--play-input <file>`.
+//!
+//! The input is what the physics tick saw (see the replay format in [`super::replay`]), so playing
+//! a file back re-simulates the match exactly, glitches included.
+
+use std::fs;
+
+use bevy::{ecs::prelude::*, log::{info, warn}};
+use serde::{Deserialize, Serialize};
+
+use super::{config::GameConfig, replay::{LastReplay, Replay}};
+
+#[derive(Serialize, Deserialize)]
+pub struct Repro {
+    pub config: GameConfig,
+    pub replay: Replay,
+}
+
+impl Repro {
+    pub fn load(path: &str) -> Result<Repro, String> {
+        let data = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        ron::from_str(&data).map_err(|e| e.to_string())
+    }
+}
+
+/// Where to write the last finished match to, from `--record-input`.
+pub struct ReproRecording(pub Option<String>);
+
+/// The command line options for recording and playing back reproductions.
+#[derive(Clone, Default)]
+pub struct ReproOptions {
+    pub record: Option<String>,
+    pub play: Option<String>,
+}
+
+impl ReproOptions {
+    /// Takes `--record-input <file>` and `--play-input <file>` out of `args`, returning the rest.
+    pub fn from_args(args: impl Iterator<Item = String>) -> (ReproOptions, Vec<String>) {
+        let mut options = ReproOptions::default();
+        let mut rest = Vec::new();
+        let mut args = args.peekable();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--record-input" if args.peek().is_some() => options.record = args.next(),
+                "--play-input" if args.peek().is_some() => options.play = args.next(),
+                _ => rest.push(arg),
+            }
+        }
+        (options, rest)
+    }
+}
+
+/// Writes the match that just ended, with the config it was played with.
+pub fn save_repro(
+    recording : Res<ReproRecording>,
+    last : Res<LastReplay>,
+    config : Res<GameConfig>,
+) {
+    let (path, replay) = match (recording.0.as_ref(), last.0.as_ref()) {
+        (Some(path), Some(replay)) => (path, replay),
+        _ => return,
+    };
+    let repro = Repro { config: config.clone(), replay: replay.clone() };
+    let result = ron::ser::to_string_pretty(&repro, Default::default())
+        .map_err(|e| e.to_string())
+        .and_then(|data| fs::write(path, data).map_err(|e| e.to_string()));
+
+    match result {
+        Ok(()) => info!("recorded input to {}", path),
+        Err(e) => warn!("could not record input to {}: {}", path, e),
+    }
+}
@@ -0,0 +1,125 @@
+use std::collections::VecDeque;
+
+use bevy::{ecs::prelude::*, input::prelude::*, math::Vec2, text::prelude::*, transform::prelude::*};
+
+use super::{Ball, BallCount, BallKind, BallSprite, GameState, Paddle, Player, Score, Velocity, Who, config::GameConfig, sandbox::SandboxControl, save::BallSnapshot, spawn_ball_of_kind};
+
+const REWIND_KEY: KeyCode = KeyCode::Back;
+/// Seconds between snapshots: ten a second.
+const SNAPSHOT_INTERVAL: f32 = 0.1;
+/// Snapshots kept: the last five seconds.
+const CAPACITY: usize = 50;
+/// Past this many balls snapshots get too big to keep fifty of, and recording stops.
+const MAX_BALLS: usize = 5_000;
+
+struct WorldSnapshot {
+    balls: Vec<BallSnapshot>,
+    player_y: f32,
+    bot_y: f32,
+    scores: Vec<(Who, i32)>,
+}
+
+/// The last few seconds of the sandbox, newest last.
+#[derive(Default)]
+pub struct RewindBuffer {
+    snapshots: VecDeque<WorldSnapshot>,
+    ticks: u32,
+}
+
+pub fn clear_rewind(
+    mut buffer : ResMut<RewindBuffer>,
+) {
+    buffer.snapshots.clear();
+    buffer.ticks = 0;
+}
+
+pub fn record_snapshot(
+    mut buffer : ResMut<RewindBuffer>,
+    balls : Query<(&Transform, &Velocity, &BallKind), With<Ball>>,
+    paddles : Query<(&Transform, Option<&Player>), With<Paddle>>,
+    scores : Query<&Score>,
+    state : Res<State<GameState>>,
+    config : Res<GameConfig>,
+) {
+    if *state.current() != GameState::Sandbox {
+        return;
+    }
+    buffer.ticks += 1;
+    if buffer.ticks % config.ticks(SNAPSHOT_INTERVAL) != 0 {
+        return;
+    }
+    let ball_count = balls.iter().count();
+    if ball_count > MAX_BALLS {
+        return;
+    }
+
+    let mut snapshot = WorldSnapshot {
+        balls: Vec::with_capacity(ball_count),
+        player_y: 0.,
+        bot_y: 0.,
+        scores: scores.iter().map(|s| (s.0, s.1)).collect(),
+    };
+    snapshot.balls.extend(balls.iter().map(|(t, v, kind)| BallSnapshot {
+        position: t.translation.truncate().into(),
+        velocity: v.0.into(),
+        kind: *kind,
+        // the sandbox never serves
+        serve_elapsed: None,
+        last_hit: None,
+    }));
+    for (t, player) in paddles.iter() {
+        match player {
+            Some(_) => snapshot.player_y = t.translation.y,
+            None => snapshot.bot_y = t.translation.y,
+        }
+    }
+
+    if buffer.snapshots.len() == CAPACITY {
+        buffer.snapshots.pop_front();
+    }
+    buffer.snapshots.push_back(snapshot);
+}
+
+/// Each press steps back one snapshot and freezes the physics, so it can be stepped forward from there.
+pub fn rewind(
+    mut commands : Commands,
+    mut buffer : ResMut<RewindBuffer>,
+    mut control : ResMut<SandboxControl>,
+    mut ball_count : ResMut<BallCount>,
+    input : Res<Input<KeyCode>>,
+    balls : Query<Entity, With<Ball>>,
+    mut paddles : Query<(&mut Transform, Option<&Player>), With<Paddle>>,
+    mut scores : Query<(&mut Text, &mut Score)>,
+    ball_sprite : Res<BallSprite>,
+    config : Res<GameConfig>,
+) {
+    if !input.just_pressed(REWIND_KEY) {
+        return;
+    }
+    let snapshot = match buffer.snapshots.pop_back() {
+        Some(snapshot) => snapshot,
+        None => return,
+    };
+    control.freeze();
+
+    for e in balls.iter() {
+        commands.entity(e).despawn();
+    }
+    for b in snapshot.balls.iter() {
+        spawn_ball_of_kind(&mut commands, &ball_sprite, &config, b.kind, Vec2::from(b.position), Vec2::from(b.velocity));
+    }
+    ball_count.0 = snapshot.balls.len() as i32;
+
+    for (mut t, player) in paddles.iter_mut() {
+        t.translation.y = match player {
+            Some(_) => snapshot.player_y,
+            None => snapshot.bot_y,
+        };
+    }
+    for (mut text, mut score) in scores.iter_mut() {
+        if let Some((_, value)) = snapshot.scores.iter().find(|(who, _)| *who == score.0) {
+            score.1 = *value;
+            text.sections[0].value = value.to_string();
+        }
+    }
+}
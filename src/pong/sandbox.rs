@@ -0,0 +1,106 @@
+use bevy::{ecs::prelude::*, ecs::schedule::ShouldRun, input::prelude::*, math::Vec2, window::prelude::*};
+
+use super::{Ball, BallCount, BallKind, BallSprite, GameState, config::GameConfig, spawn_ball_of_kind, transition::Transition};
+
+const CLEAR_KEY: KeyCode = KeyCode::C;
+const FREEZE_KEY: KeyCode = KeyCode::F;
+const STEP_KEY: KeyCode = KeyCode::N;
+/// A dragged-out ball covers the length of the drag in this many seconds.
+const DRAG_SECONDS: f32 = 0.5;
+
+/// Physics controls for `GameState::Sandbox`.
+#[derive(Default)]
+pub struct SandboxControl {
+    frozen: bool,
+    step: bool,
+    /// World position the mouse was pressed at, while dragging out a ball.
+    drag_start: Option<Vec2>,
+}
+
+impl SandboxControl {
+    pub fn freeze(&mut self) {
+        self.frozen = true;
+    }
+
+    /// Whether the physics tick should run, given what the fixed timestep says.
+    pub fn should_run(&mut self, should_run: ShouldRun) -> ShouldRun {
+        if !self.frozen {
+            return should_run;
+        }
+        if self.step {
+            self.step = false;
+            return ShouldRun::Yes;
+        }
+        ShouldRun::No
+    }
+}
+
+pub fn reset_sandbox(
+    mut control : ResMut<SandboxControl>,
+) {
+    *control = SandboxControl::default();
+}
+
+/// The window's cursor position in world units.
+pub fn cursor_world(window: &Window, config: &GameConfig) -> Option<Vec2> {
+    Some(window_to_world(window, window.cursor_position()?, config))
+}
+
+/// A position in window pixels, from the bottom left corner, in world units.
+pub fn window_to_world(window: &Window, position: Vec2, config: &GameConfig) -> Vec2 {
+    let size = Vec2::new(window.width(), window.height());
+    (position - size / 2.) * config.view_scale(window)
+}
+
+/// Press to place a ball, drag to aim it and release to send it off.
+pub fn spawn_ball_at_cursor(
+    mut commands : Commands,
+    mut control : ResMut<SandboxControl>,
+    mut ball_count : ResMut<BallCount>,
+    mouse : Res<Input<MouseButton>>,
+    windows : Res<Windows>,
+    ball_sprite : Res<BallSprite>,
+    config : Res<GameConfig>,
+) {
+    let cursor = match windows.get_primary().and_then(|window| cursor_world(window, &config)) {
+        Some(cursor) => cursor,
+        None => return,
+    };
+
+    if mouse.just_pressed(MouseButton::Left) {
+        control.drag_start = Some(cursor);
+    }
+    if mouse.just_released(MouseButton::Left) {
+        if let Some(start) = control.drag_start.take() {
+            let velocity = (cursor - start) / DRAG_SECONDS * config.timestep() as f32;
+            spawn_ball_of_kind(&mut commands, &ball_sprite, &config, BallKind::Normal, start, velocity);
+            ball_count.0 += 1;
+        }
+    }
+}
+
+pub fn sandbox_keys(
+    mut commands : Commands,
+    mut input : ResMut<Input<KeyCode>>,
+    mut control : ResMut<SandboxControl>,
+    mut ball_count : ResMut<BallCount>,
+    mut transition : ResMut<Transition>,
+    balls : Query<Entity, With<Ball>>,
+) {
+    if input.just_pressed(CLEAR_KEY) {
+        for e in balls.iter() {
+            commands.entity(e).despawn();
+        }
+        ball_count.0 = 0;
+    }
+    if input.just_pressed(FREEZE_KEY) {
+        control.frozen = !control.frozen;
+    }
+    if input.just_pressed(STEP_KEY) && control.frozen {
+        control.step = true;
+    }
+    if input.just_pressed(KeyCode::Escape) {
+        input.reset(KeyCode::Escape);
+        transition.fade_to(GameState::Menu);
+    }
+}
@@ -0,0 +1,244 @@
+use std::{fs, time::Duration};
+
+use bevy::{ecs::prelude::*, input::prelude::*, log::{info, warn}, math::Vec2, text::prelude::*, transform::prelude::*};
+use rand_pcg::Pcg32;
+use serde::{Deserialize, Serialize};
+
+use super::{Ball, BallCount, BallSprite, GameRng, MatchTick, Paddle, Player, Score, ServeTimer, Velocity, Who, ball_kind::BallKind, breakout::{self, Brick, LastHit}, combo::{Combo, ComboText}, config::GameConfig, exits::ExitQueue, power_shot::PowerShot, replay::ReplayRecorder, serve::Serve, sets::Sets, spawn_ball_of_kind, stamina::Stamina};
+
+const SAVE_PATH: &str = "savegame.ron";
+pub const SAVE_KEY: KeyCode = KeyCode::F5;
+const LOAD_KEY: KeyCode = KeyCode::F9;
+
+/// Everything needed to put a match back exactly where it was left. Fields added since the first
+/// version default, so older saves still load.
+#[derive(Serialize, Deserialize)]
+struct MatchSnapshot {
+    scores: Vec<(Who, i32)>,
+    player_y: f32,
+    bot_y: f32,
+    balls: Vec<BallSnapshot>,
+    rng: Pcg32,
+    #[serde(default)]
+    tick: u32,
+    #[serde(default)]
+    sets: Sets,
+    #[serde(default)]
+    serve: Serve,
+    #[serde(default)]
+    combo: Combo,
+    #[serde(default = "full_stamina")]
+    stamina: f32,
+    #[serde(default)]
+    power_shot: PowerShot,
+    /// Positions of the bricks still standing; `None` in saves from before bricks were kept.
+    #[serde(default)]
+    bricks: Option<Vec<[f32; 2]>>,
+}
+
+fn full_stamina() -> f32 {
+    Stamina::default().0
+}
+
+/// Set by the main menu's "Continue" to load the save once the match it opens is set up.
+#[derive(Default)]
+pub struct PendingLoad(pub bool);
+
+/// Where the match is saved to and loaded from.
+pub struct SaveFile(pub String);
+
+impl Default for SaveFile {
+    fn default() -> Self {
+        Self(SAVE_PATH.to_string())
+    }
+}
+
+/// Whether there is a saved match to continue.
+pub fn has_save(file: &SaveFile) -> bool {
+    fs::metadata(&file.0).is_ok()
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BallSnapshot {
+    pub position: [f32; 2],
+    pub velocity: [f32; 2],
+    #[serde(default)]
+    pub kind: BallKind,
+    /// Seconds the ball has waited for its serve, if it hasn't been served yet.
+    #[serde(default)]
+    pub serve_elapsed: Option<f32>,
+    /// Who gets the points for the bricks it breaks.
+    #[serde(default)]
+    pub last_hit: Option<Who>,
+}
+
+pub fn save_match(
+    input : Res<Input<KeyCode>>,
+    balls : Query<(&Transform, &Velocity, &BallKind, &LastHit, Option<&ServeTimer>), With<Ball>>,
+    paddles : Query<(&Transform, Option<&Player>, Option<&Stamina>, Option<&PowerShot>), With<Paddle>>,
+    bricks : Query<&Transform, With<Brick>>,
+    scores : Query<&Score>,
+    rng : Res<GameRng>,
+    tick : Res<MatchTick>,
+    sets : Res<Sets>,
+    serve : Res<Serve>,
+    combo : Res<Combo>,
+    file : Res<SaveFile>,
+) {
+    if !input.just_pressed(SAVE_KEY) {
+        return;
+    }
+
+    let mut snapshot = MatchSnapshot {
+        scores: scores.iter().map(|s| (s.0, s.1)).collect(),
+        player_y: 0.,
+        bot_y: 0.,
+        balls: balls.iter().map(|(t, v, kind, last_hit, serve)| BallSnapshot {
+            position: t.translation.truncate().into(),
+            velocity: v.0.into(),
+            kind: *kind,
+            serve_elapsed: serve.map(|timer| timer.0.elapsed_secs()),
+            last_hit: last_hit.0,
+        }).collect(),
+        rng: rng.0.clone(),
+        tick: tick.0,
+        sets: sets.clone(),
+        serve: serve.clone(),
+        combo: combo.clone(),
+        stamina: full_stamina(),
+        power_shot: PowerShot::default(),
+        bricks: Some(bricks.iter().map(|t| t.translation.truncate().into()).collect()),
+    };
+
+    for (t, player, stamina, shot) in paddles.iter() {
+        match player {
+            Some(_) => {
+                snapshot.player_y = t.translation.y;
+                snapshot.stamina = stamina.map_or(snapshot.stamina, |stamina| stamina.0);
+                snapshot.power_shot = shot.cloned().unwrap_or_default();
+            }
+            None => snapshot.bot_y = t.translation.y,
+        }
+    }
+
+    let result = ron::ser::to_string_pretty(&snapshot, Default::default())
+        .map_err(|e| e.to_string())
+        .and_then(|data| fs::write(&file.0, data).map_err(|e| e.to_string()));
+
+    match result {
+        Ok(()) => info!("saved match to {}", file.0),
+        Err(e) => warn!("could not save match: {}", e),
+    }
+}
+
+/// Puts the saved match back on F9, or once a match opened by the main menu's "Continue" is set up.
+/// A loaded match can't be re-simulated from the seed the replay recorder started from, so its
+/// replay is dropped.
+pub fn load_match(
+    mut commands : Commands,
+    input : Res<Input<KeyCode>>,
+    mut pending : ResMut<PendingLoad>,
+    balls : Query<Entity, With<Ball>>,
+    bricks : Query<Entity, With<Brick>>,
+    mut paddles : Query<(&mut Transform, Option<&Player>, Option<&mut Stamina>, Option<&mut PowerShot>), With<Paddle>>,
+    mut sided : Query<&mut Transform, (Or<(With<Score>, With<ComboText>)>, Without<Paddle>)>,
+    mut scores : Query<(&mut Text, &mut Score)>,
+    // one parameter for the match progress keeps the system within Bevy's parameter limit
+    mut progress : (ResMut<MatchTick>, ResMut<Sets>, ResMut<Serve>, ResMut<Combo>),
+    mut ball_count : ResMut<BallCount>,
+    mut rng : ResMut<GameRng>,
+    mut exits : ResMut<ExitQueue>,
+    mut recorder : ResMut<ReplayRecorder>,
+    ball_sprite : Res<BallSprite>,
+    config : Res<GameConfig>,
+    file : Res<SaveFile>,
+) {
+    // the paddles only exist once the commands spawning the match have been applied
+    let requested = input.just_pressed(LOAD_KEY) || (pending.0 && paddles.iter().next().is_some());
+    if !requested {
+        return;
+    }
+    pending.0 = false;
+
+    let snapshot: MatchSnapshot = match fs::read_to_string(&file.0)
+        .map_err(|e| e.to_string())
+        .and_then(|data| ron::from_str(&data).map_err(|e| e.to_string())) {
+        Ok(snapshot) => snapshot,
+        Err(e) => {
+            warn!("could not load match from {}: {}", file.0, e);
+            return;
+        }
+    };
+
+    for e in balls.iter() {
+        commands.entity(e).despawn();
+    }
+    exits.clear();
+
+    for b in snapshot.balls.iter() {
+        let ball = spawn_ball_of_kind(&mut commands, &ball_sprite, &config, b.kind, Vec2::from(b.position), Vec2::from(b.velocity));
+        commands.entity(ball).insert(LastHit(b.last_hit));
+        // saves from before serve timers were kept per ball only tell a waiting ball by it standing still
+        let waiting = b.serve_elapsed.or(if b.velocity == [0., 0.] { Some(0.) } else { None });
+        if let Some(elapsed) = waiting {
+            let mut timer = ServeTimer::new(&config);
+            timer.0.set_elapsed(Duration::from_secs_f32(elapsed));
+            commands.entity(ball).insert(timer);
+        }
+    }
+
+    if let Some(standing) = snapshot.bricks.as_ref() {
+        for e in bricks.iter() {
+            commands.entity(e).despawn();
+        }
+        for (row, position) in breakout::brick_grid() {
+            if standing.iter().any(|brick| Vec2::from(*brick).distance(position) < 1.) {
+                breakout::spawn_brick(&mut commands, &ball_sprite, row, position);
+            }
+        }
+    }
+
+    let (tick, sets, serve, combo) = &mut progress;
+    // everything tied to a side is mirrored when the ends are swapped, see sets::switch_sides
+    let mirror = sets.swapped() != snapshot.sets.swapped();
+    tick.0 = snapshot.tick;
+    **sets = snapshot.sets;
+    **serve = snapshot.serve;
+    **combo = snapshot.combo;
+
+    for (mut t, player, stamina, shot) in paddles.iter_mut() {
+        if mirror {
+            t.translation.x = -t.translation.x;
+        }
+        match player {
+            Some(_) => {
+                t.translation.y = snapshot.player_y;
+                if let Some(mut stamina) = stamina {
+                    stamina.0 = snapshot.stamina;
+                }
+                if let Some(mut shot) = shot {
+                    *shot = snapshot.power_shot.clone();
+                }
+            }
+            None => t.translation.y = snapshot.bot_y,
+        }
+    }
+    if mirror {
+        for mut t in sided.iter_mut() {
+            t.translation.x = -t.translation.x;
+        }
+    }
+
+    for (mut text, mut score) in scores.iter_mut() {
+        if let Some((_, value)) = snapshot.scores.iter().find(|(who, _)| *who == score.0) {
+            score.1 = *value;
+            text.sections[0].value = value.to_string();
+        }
+    }
+
+    ball_count.0 = snapshot.balls.len() as i32;
+    rng.0 = snapshot.rng;
+    recorder.discard();
+
+    info!("loaded match from {}", file.0);
+}
@@ -0,0 +1,162 @@
+use bevy::{app::prelude::*, core_pipeline::{draw_2d_graph, node, Transparent2d}, ecs::prelude::*, render::{camera::{ActiveCameras, ExtractedCameraNames, OrthographicCameraBundle, OrthographicProjection}, color::Color, render_graph::{Node, NodeRunError, RenderGraph, RenderGraphContext, SlotValue}, render_phase::RenderPhase, renderer::RenderContext, RenderApp, RenderStage}, text::prelude::*, transform::prelude::*, window::{CreateWindow, WindowDescriptor, WindowId, WindowResized}};
+
+use super::{MatchTick, Score, UiFont, Who, config::GameConfig, sets::Sets, sim_clock::SimClock};
+
+const CAMERA_NAME: &str = "scoreboard_2d";
+const PASS_DRIVER: &str = "scoreboard_pass_driver";
+/// Every 2d camera draws every sprite and text, so the scoreboard is laid out this far along x,
+/// well clear of anything the court camera shows.
+const ORIGIN_X: f32 = 100_000.;
+/// World units the scoreboard is laid out in, fitted to the window like the court is.
+const VIEW_WIDTH: f32 = 1280.;
+const VIEW_HEIGHT: f32 = 720.;
+const SCORE_SIZE: f32 = 320.;
+const CLOCK_SIZE: f32 = 120.;
+
+/// A second window with just the scores, sets and match time in huge text, for casting a local
+/// tournament on a second monitor. Closing it quits the game like closing the main window does.
+pub struct ScoreboardPlugin;
+
+impl Plugin for ScoreboardPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ScoreboardWindow(WindowId::new()))
+            .add_startup_system(open_scoreboard)
+            .add_startup_system_to_stage("game_setup", spawn_scoreboard)
+            .add_system(fit_scoreboard_camera)
+            .add_system(update_scoreboard);
+
+        let render_app = app.sub_app_mut(RenderApp);
+        render_app.add_system_to_stage(RenderStage::Extract, extract_scoreboard_phase);
+        let mut graph = render_app.world.get_resource_mut::<RenderGraph>().unwrap();
+        graph.add_node(PASS_DRIVER, ScoreboardPassDriver);
+        graph.add_node_edge(node::MAIN_PASS_DEPENDENCIES, PASS_DRIVER).unwrap();
+    }
+}
+
+struct ScoreboardWindow(WindowId);
+
+/// The camera drawing the scoreboard window, left alone by the court camera's fitting and shake.
+#[derive(Component)]
+pub struct ScoreboardCamera;
+
+#[derive(Component)]
+enum ScoreboardLine { Score, Clock }
+
+fn open_scoreboard(
+    mut commands : Commands,
+    mut create_window : EventWriter<CreateWindow>,
+    mut active_cameras : ResMut<ActiveCameras>,
+    window : Res<ScoreboardWindow>,
+) {
+    create_window.send(CreateWindow {
+        id: window.0,
+        descriptor: WindowDescriptor {
+            width: VIEW_WIDTH,
+            height: VIEW_HEIGHT,
+            title: "Pong scoreboard".to_string(),
+            ..Default::default()
+        },
+    });
+
+    let mut camera = OrthographicCameraBundle::new_2d();
+    camera.camera.name = Some(CAMERA_NAME.to_string());
+    camera.camera.window = window.0;
+    camera.transform.translation.x = ORIGIN_X;
+    commands.spawn_bundle(camera).insert(ScoreboardCamera);
+    active_cameras.add(CAMERA_NAME);
+}
+
+fn spawn_scoreboard(
+    mut commands : Commands,
+    font : Res<UiFont>,
+) {
+    let lines = [(ScoreboardLine::Score, SCORE_SIZE, 80.), (ScoreboardLine::Clock, CLOCK_SIZE, -220.)];
+    for (line, size, y) in lines {
+        commands.spawn_bundle(Text2dBundle {
+            text: Text::with_section(
+                "", TextStyle {
+                    font: font.0.clone(),
+                    font_size: size,
+                    color: Color::WHITE,
+                }, TextAlignment {
+                    vertical: VerticalAlign::Center,
+                    horizontal: HorizontalAlign::Center,
+                }),
+            transform: Transform::from_xyz(ORIGIN_X, y, 0.),
+            ..Default::default()
+        })
+            .insert(line);
+    }
+}
+
+/// Fits the scoreboard into its window whenever that one is resized.
+fn fit_scoreboard_camera(
+    mut resized : EventReader<WindowResized>,
+    mut cameras : Query<&mut OrthographicProjection, With<ScoreboardCamera>>,
+    window : Res<ScoreboardWindow>,
+) {
+    for e in resized.iter().filter(|e| e.id == window.0 && e.width > 0. && e.height > 0.) {
+        for mut projection in cameras.iter_mut() {
+            projection.scale = f32::max(VIEW_WIDTH / e.width, VIEW_HEIGHT / e.height);
+        }
+    }
+}
+
+fn update_scoreboard(
+    scores : Query<&Score>,
+    mut lines : Query<(&ScoreboardLine, &mut Text)>,
+    sets : Res<Sets>,
+    tick : Res<MatchTick>,
+    clock : Res<SimClock>,
+    config : Res<GameConfig>,
+) {
+    // counters are keyed by the side balls go out on, so the player's points are under OPPONENT
+    let points = |side: Who| scores.iter().find(|s| s.0 == side).map(|s| s.1).unwrap_or_default();
+    let (player, bot) = (points(Who::OPPONENT), points(Who::PLAYER));
+    // laid out the way round the court is being played
+    let (left, right) = if sets.swapped() { (bot, player) } else { (player, bot) };
+    let seconds = (tick.0 as f64 * clock.timestep) as u64;
+
+    for (line, mut text) in lines.iter_mut() {
+        let value = match line {
+            ScoreboardLine::Score => format!("{} - {}", left, right),
+            ScoreboardLine::Clock if config.sets_to_win > 1 => {
+                let (left, right) = if sets.swapped() { (Who::OPPONENT, Who::PLAYER) } else { (Who::PLAYER, Who::OPPONENT) };
+                format!("{}:{:02}   sets {} - {}", seconds / 60, seconds % 60, sets.won(left), sets.won(right))
+            }
+            ScoreboardLine::Clock => format!("{}:{:02}", seconds / 60, seconds % 60),
+        };
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+    }
+}
+
+/// The court camera's phases are set up by the 2d pipeline itself; the scoreboard camera is
+/// drawn with the same phase.
+fn extract_scoreboard_phase(
+    mut commands : Commands,
+    active_cameras : Res<ActiveCameras>,
+) {
+    if let Some(entity) = active_cameras.get(CAMERA_NAME).and_then(|camera| camera.entity) {
+        commands.get_or_spawn(entity).insert(RenderPhase::<Transparent2d>::default());
+    }
+}
+
+/// Runs the 2d pass a second time, for the scoreboard camera.
+struct ScoreboardPassDriver;
+
+impl Node for ScoreboardPassDriver {
+    fn run(
+        &self,
+        graph: &mut RenderGraphContext,
+        _render_context: &mut RenderContext,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let cameras = world.get_resource::<ExtractedCameraNames>().unwrap();
+        if let Some(camera) = cameras.entities.get(CAMERA_NAME) {
+            graph.run_sub_graph(draw_2d_graph::NAME, vec![SlotValue::Entity(*camera)])?;
+        }
+        Ok(())
+    }
+}
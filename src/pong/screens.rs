@@ -0,0 +1,249 @@
+use bevy::{app::AppExit, ecs::prelude::*, input::{gamepad::{GamepadButton, GamepadButtonType}, prelude::*}, log::warn, math::Vec2, text::prelude::*, transform::prelude::*, window::{WindowFocused, WindowResized}};
+
+use super::{GameState, UiFont, Who, config::{self, ConfigOverrides, GameConfig}, courts::CourtLayouts, frame_limit::FRAME_LIMITS, gamepad::ActivePad, handheld, idle::IdleTimer, menu::{MenuAction, MenuCursor, MenuEvent, spawn_menu_items}, rating::{self, LastRatingChange, Profile}, save::{self, PendingLoad}, serve::FirstServer, transition::Transition};
+
+const SCREEN_Z: f32 = 10.0;
+const REMATCH_KEY: KeyCode = KeyCode::R;
+/// Pixels per second each press of left or right changes a paddle speed by.
+const SPEED_STEP: f32 = 60.;
+
+/// Marks text belonging to the current menu or overlay screen, despawned when leaving it.
+#[derive(Component)]
+pub struct ScreenText;
+
+/// Who won the last match, shown on the game over screen.
+pub struct Winner(pub Who);
+
+pub fn spawn_screen_text(
+    commands: &mut Commands,
+    font: &UiFont,
+    value: &str,
+    pos: Vec2,
+    size: f32,
+) -> Entity {
+    commands.spawn_bundle(Text2dBundle {
+        text: Text::with_section(
+            value, TextStyle {
+                font: font.0.clone(),
+                font_size: size,
+                color: bevy::render::color::Color::WHITE,
+            }, TextAlignment {
+                vertical: VerticalAlign::Center,
+                horizontal: HorizontalAlign::Center,
+            }),
+        transform: Transform::from_xyz(pos.x, pos.y, SCREEN_Z),
+        ..Default::default()
+    })
+        .insert(ScreenText)
+        .id()
+}
+
+pub fn spawn_menu(
+    mut commands: Commands,
+    mut cursor: ResMut<MenuCursor>,
+    font: Res<UiFont>,
+    save_file: Res<save::SaveFile>,
+) {
+    spawn_screen_text(&mut commands, &font, "PONG", Vec2::new(0., 150.), 120.);
+    let mut items = vec![MenuAction::Play];
+    if save::has_save(&save_file) {
+        items.push(MenuAction::ContinueSaved);
+    }
+    items.extend([MenuAction::Court, MenuAction::Sandbox, MenuAction::WatchReplay, MenuAction::Settings, MenuAction::Quit]);
+    spawn_menu_items(&mut commands, &font, &mut cursor, &items, 0.);
+}
+
+pub fn spawn_pause_menu(
+    mut commands: Commands,
+    mut cursor: ResMut<MenuCursor>,
+    pad: Res<ActivePad>,
+    idle: Res<IdleTimer>,
+    font: Res<UiFont>,
+) {
+    spawn_screen_text(&mut commands, &font, "Paused", Vec2::new(0., 150.), 100.);
+    if pad.disconnected {
+        spawn_screen_text(&mut commands, &font, "Controller disconnected - reconnect it or use the keyboard", Vec2::new(0., 80.), 30.);
+    } else if idle.paused {
+        spawn_screen_text(&mut commands, &font, "Paused for inactivity - the match ends if it stays idle", Vec2::new(0., 80.), 30.);
+    }
+    spawn_menu_items(&mut commands, &font, &mut cursor,
+        &[MenuAction::Resume, MenuAction::Restart, MenuAction::Settings, MenuAction::QuitToMenu], 0.);
+}
+
+pub fn spawn_settings(
+    mut commands: Commands,
+    mut cursor: ResMut<MenuCursor>,
+    font: Res<UiFont>,
+) {
+    spawn_screen_text(&mut commands, &font, "Settings", Vec2::new(0., 330.), 60.);
+    spawn_menu_items(&mut commands, &font, &mut cursor,
+        &[MenuAction::PaddleSpeed, MenuAction::BotSpeed, MenuAction::Graphics, MenuAction::VSync, MenuAction::FrameLimit, MenuAction::PlayerShape, MenuAction::BotShape, MenuAction::Stamina, MenuAction::Assist, MenuAction::Controls, MenuAction::Back], 270.);
+}
+
+pub fn pause_input(
+    mut input : ResMut<Input<KeyCode>>,
+    mut buttons : ResMut<Input<GamepadButton>>,
+    pad : Res<ActivePad>,
+    mut state : ResMut<State<GameState>>,
+) {
+    if input.just_pressed(KeyCode::Escape) {
+        input.reset(KeyCode::Escape);
+        let _ = state.push(GameState::Paused);
+    }
+    if let Some(start) = pad.button(GamepadButtonType::Start).filter(|start| buttons.just_pressed(*start)) {
+        buttons.reset(start);
+        let _ = state.push(GameState::Paused);
+    }
+}
+
+/// Pauses when the window loses focus or is minimized (reported as a resize to zero),
+/// so the bot can't keep scoring while nobody is looking.
+pub fn auto_pause(
+    mut focused : EventReader<WindowFocused>,
+    mut resized : EventReader<WindowResized>,
+    mut state : ResMut<State<GameState>>,
+) {
+    let lost_focus = focused.iter().any(|e| !e.focused);
+    let minimized = resized.iter().any(|e| e.width == 0. || e.height == 0.);
+    if lost_focus || minimized {
+        let _ = state.push(GameState::Paused);
+    }
+}
+
+pub fn handle_menu_events(
+    mut events : EventReader<MenuEvent>,
+    mut state : ResMut<State<GameState>>,
+    mut transition : ResMut<Transition>,
+    mut config : ResMut<GameConfig>,
+    mut pending_load : ResMut<PendingLoad>,
+    mut overrides : ResMut<ConfigOverrides>,
+    mut first_server : ResMut<FirstServer>,
+    mut profile : ResMut<Profile>,
+    mut exit : EventWriter<AppExit>,
+    courts : Res<CourtLayouts>,
+) {
+    for event in events.iter() {
+        match (event.action, event.delta) {
+            (MenuAction::Play, 0) => {
+                *first_server = FirstServer::default();
+                transition.slide_to(GameState::Playing);
+            }
+            (MenuAction::ContinueSaved, 0) => {
+                *first_server = FirstServer::default();
+                pending_load.0 = true;
+                transition.slide_to(GameState::Playing);
+            }
+            (MenuAction::Sandbox, 0) => transition.fade_to(GameState::Sandbox),
+            (MenuAction::WatchReplay, 0) => transition.fade_to(GameState::Replay),
+            (MenuAction::Restart, 0) => transition.fade_to(GameState::Playing),
+            (MenuAction::Settings, 0) => { let _ = state.push(GameState::Settings); }
+            (MenuAction::QuitToMenu, 0) => transition.fade_to(GameState::Menu),
+            (MenuAction::Quit, 0) => exit.send(AppExit),
+            (MenuAction::HandheldPreset, 0) | (MenuAction::KeepDefaults, 0) => {
+                if event.action == MenuAction::HandheldPreset {
+                    handheld::apply_preset(&mut config);
+                    // saved over the file's own values, so the command line arguments aren't kept
+                    match config::load() {
+                        Ok(mut file) => {
+                            handheld::apply_preset(&mut file);
+                            if let Err(e) = config::save(&file) {
+                                warn!("could not save {}: {}", config::CONFIG_PATH, e);
+                            }
+                        }
+                        Err(e) => warn!("not saving the handheld preset over {}: {}", config::CONFIG_PATH, e),
+                    }
+                }
+                profile.handheld_prompt_answered = true;
+                if let Err(e) = rating::save(&profile) {
+                    warn!("could not save {}: {}", rating::PROFILE_PATH, e);
+                }
+                transition.fade_to(GameState::Menu);
+            }
+            (MenuAction::Resume, 0) | (MenuAction::Back, 0) => {
+                if *state.current() != GameState::Menu {
+                    let _ = state.pop();
+                }
+            }
+            (MenuAction::PaddleSpeed, delta) if delta != 0 => {
+                config.paddle_speed = (config.paddle_speed + delta as f32 * SPEED_STEP).max(SPEED_STEP);
+            }
+            (MenuAction::BotSpeed, delta) if delta != 0 => {
+                config.bot_paddle_speed = (config.bot_paddle_speed + delta as f32 * SPEED_STEP).max(SPEED_STEP);
+            }
+            (MenuAction::Graphics, delta) if delta != 0 => {
+                config.graphics_quality = config.graphics_quality.step(delta);
+            }
+            (MenuAction::VSync, _) => config.vsync = !config.vsync,
+            (MenuAction::Stamina, _) => config.stamina = !config.stamina,
+            (MenuAction::Assist, _) => config.trajectory_assist = !config.trajectory_assist,
+            (MenuAction::FrameLimit, delta) if delta != 0 => {
+                let index = FRAME_LIMITS.iter().position(|fps| *fps == config.frame_limit).unwrap_or_default() as i32;
+                config.frame_limit = FRAME_LIMITS[(index + delta).clamp(0, FRAME_LIMITS.len() as i32 - 1) as usize];
+            }
+            (MenuAction::PlayerShape, delta) if delta != 0 => {
+                config.player_paddle_shape = config.player_paddle_shape.step(delta);
+            }
+            (MenuAction::BotShape, delta) if delta != 0 => {
+                config.bot_paddle_shape = config.bot_paddle_shape.step(delta);
+            }
+            (MenuAction::Court, delta) => {
+                // activating it steps forward too
+                if let Some(layout) = courts.step(&config.court, if delta == 0 { 1 } else { delta }) {
+                    layout.apply(&mut config);
+                }
+            }
+            (MenuAction::Controls, delta) if delta != 0 => {
+                config.input.player = config.input.player.step(delta);
+            }
+            _ => {}
+        }
+        // kept over reloads of the config file, see config::watch_config
+        if event.action.is_setting() {
+            overrides.changed_setting(event.action);
+        }
+    }
+}
+
+pub fn spawn_game_over(
+    mut commands: Commands,
+    mut cursor: ResMut<MenuCursor>,
+    font: Res<UiFont>,
+    winner: Res<Winner>,
+    last_rating: Res<LastRatingChange>,
+) {
+    let title = match winner.0 {
+        Who::PLAYER => "You win!",
+        Who::OPPONENT => "The bot wins!",
+    };
+    spawn_screen_text(&mut commands, &font, title, Vec2::new(0., 150.), 100.);
+    if let Some(change) = last_rating.0.as_ref() {
+        let line = format!("Rating vs {:?} bot: {:.0} ({:+.0})", change.difficulty, change.rating, change.delta);
+        spawn_screen_text(&mut commands, &font, &line, Vec2::new(0., 80.), 30.);
+    }
+    spawn_menu_items(&mut commands, &font, &mut cursor,
+        &[MenuAction::Restart, MenuAction::QuitToMenu], 0.);
+    spawn_screen_text(&mut commands, &font, "Press R for a rematch", Vec2::new(0., -150.), 30.);
+}
+
+/// Starts the next match straight from the game over screen. Mode, difficulty and controls all
+/// live in the config, so the rematch plays like the match before it, except the other side
+/// serves first.
+pub fn rematch_input(
+    input : Res<Input<KeyCode>>,
+    mut transition : ResMut<Transition>,
+    mut first_server : ResMut<FirstServer>,
+) {
+    if input.just_pressed(REMATCH_KEY) && !transition.is_running() {
+        first_server.swap();
+        transition.fade_to(GameState::Playing);
+    }
+}
+
+pub fn despawn_screen(
+    mut commands: Commands,
+    texts: Query<Entity, With<ScreenText>>,
+) {
+    for e in texts.iter() {
+        commands.entity(e).despawn();
+    }
+}
@@ -0,0 +1,122 @@
+use bevy::{ecs::prelude::*, render::color::Color, text::prelude::*, transform::prelude::*};
+use serde::{Deserialize, Serialize};
+
+use super::{MatchEntity, Score, ScoreEvent, UiFont, Who, config::GameConfig, sets::Sets};
+
+const INDICATOR_GAP: f32 = 50.;
+const INDICATOR_Z: f32 = 5.;
+
+/// Who serves next, for `GameConfig::serve_every`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Serve {
+    pub server: Who,
+    /// Points scored since the serve last changed hands.
+    points: u32,
+}
+
+impl Default for Serve {
+    fn default() -> Self {
+        Self::first(Who::PLAYER)
+    }
+}
+
+impl Serve {
+    /// The serve at the start of a match `server` serves first in.
+    pub fn first(server: Who) -> Self {
+        Self { server, points: 0 }
+    }
+
+    pub fn receiver(&self) -> Who {
+        match self.server {
+            Who::PLAYER => Who::OPPONENT,
+            Who::OPPONENT => Who::PLAYER,
+        }
+    }
+}
+
+/// Who serves first in the next match: the player in a match started from the menu, swapping
+/// over each rematch.
+#[derive(Default)]
+pub struct FirstServer(pub Who);
+
+impl FirstServer {
+    pub fn swap(&mut self) {
+        self.0 = match self.0 {
+            Who::PLAYER => Who::OPPONENT,
+            Who::OPPONENT => Who::PLAYER,
+        };
+    }
+}
+
+pub fn reset_serve(
+    mut serve : ResMut<Serve>,
+    first : Res<FirstServer>,
+) {
+    *serve = Serve::first(first.0);
+}
+
+/// Hands the serve over every `serve_every` points, whoever scored them.
+pub fn alternate_serve(
+    mut serve : ResMut<Serve>,
+    mut scores : EventReader<ScoreEvent>,
+    config : Res<GameConfig>,
+) {
+    if config.serve_every == 0 {
+        return;
+    }
+    for _ in scores.iter() {
+        serve.points += 1;
+        if serve.points >= config.serve_every {
+            serve.points = 0;
+            serve.server = serve.receiver();
+        }
+    }
+}
+
+/// Points at the receiver from beside the server's score.
+#[derive(Component)]
+pub struct ServeIndicator;
+
+pub fn spawn_serve_indicator(
+    mut commands : Commands,
+    font : Res<UiFont>,
+) {
+    commands.spawn_bundle(Text2dBundle {
+        text: Text::with_section(
+            "", TextStyle {
+                font: font.0.clone(),
+                font_size: 60.,
+                color: Color::YELLOW,
+            }, Default::default()),
+        ..Default::default()
+    })
+        .insert(ServeIndicator)
+        .insert(MatchEntity);
+}
+
+pub fn update_serve_indicator(
+    serve : Res<Serve>,
+    sets : Res<Sets>,
+    scores : Query<(&Score, &Transform), Without<ServeIndicator>>,
+    mut indicator : Query<(&mut Text, &mut Transform), With<ServeIndicator>>,
+    config : Res<GameConfig>,
+) {
+    // counters are keyed by the side balls go out on, so the receiver's counts the server's points
+    let score = scores.iter().find(|(score, _)| score.0 == serve.receiver()).map(|(_, t)| t.translation);
+    let towards = sets.side_of(serve.receiver());
+    let label = match score {
+        Some(_) if config.serve_every > 0 => if towards > 0. { ">" } else { "<" },
+        _ => "",
+    };
+
+    for (mut text, mut t) in indicator.iter_mut() {
+        if text.sections[0].value != label {
+            text.sections[0].value = label.to_string();
+        }
+        if let Some(score) = score {
+            t.translation.x = score.x - INDICATOR_GAP;
+            t.translation.y = score.y;
+            t.translation.z = INDICATOR_Z;
+        }
+    }
+}
@@ -0,0 +1,217 @@
+use bevy::{core::prelude::*, ecs::prelude::*, math::Vec2, render::color::Color, text::prelude::*, transform::prelude::*};
+use serde::{Deserialize, Serialize};
+
+use super::{Ball, BallCount, GameState, MatchEntity, Paddle, Score, UiFont, Who, combo::ComboText, config::GameConfig, exits::ExitQueue, screens::spawn_screen_text};
+
+/// Seconds the "switching sides" screen stays up between sets.
+const SWITCH_TIME: f32 = 2.;
+const DEUCE_MARGIN: f32 = 40.;
+const DEUCE_Z: f32 = 5.;
+
+/// Sets won so far in the match, and which end of the court each side plays from.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Sets {
+    player: u32,
+    bot: u32,
+    /// The player is playing from the right end.
+    swapped: bool,
+    /// Winner of the set that just ended, until `switch_sides` starts the next one.
+    #[serde(skip)]
+    ended: Option<Who>,
+}
+
+impl Sets {
+    pub fn won(&self, who: Who) -> u32 {
+        match who {
+            Who::PLAYER => self.player,
+            Who::OPPONENT => self.bot,
+        }
+    }
+
+    pub fn swapped(&self) -> bool {
+        self.swapped
+    }
+
+    /// -1 if `who` plays from the left end, 1 if from the right.
+    pub fn side_of(&self, who: Who) -> f32 {
+        if (who == Who::PLAYER) != self.swapped { -1. } else { 1. }
+    }
+
+    /// Whose goal is at the end of the court `x` is on.
+    pub fn goal_at(&self, x: f32) -> Who {
+        if x * self.side_of(Who::PLAYER) > 0. { Who::PLAYER } else { Who::OPPONENT }
+    }
+
+    /// Whether a side has won the sets it needs to win the match.
+    pub fn is_over(&self, config: &GameConfig) -> bool {
+        self.player.max(self.bot) >= config.sets_to_win.max(1)
+    }
+
+    /// Counts the set `winner` just reached the score limit in. True once that wins the match;
+    /// otherwise the next set starts at the end of the tick.
+    pub fn finish_set(&mut self, winner: Who, config: &GameConfig) -> bool {
+        if self.is_over(config) {
+            return true;
+        }
+        if self.ended.is_some() {
+            return false;
+        }
+        match winner {
+            Who::PLAYER => self.player += 1,
+            Who::OPPONENT => self.bot += 1,
+        }
+        if self.is_over(config) {
+            return true;
+        }
+        self.ended = Some(winner);
+        false
+    }
+}
+
+/// Whether `points` against `other` wins the set, needing a two point lead with `GameConfig::win_by_two`.
+pub fn set_won(points: i32, other: i32, config: &GameConfig) -> bool {
+    points >= config.score_limit && (!config.win_by_two || points - other >= 2)
+}
+
+pub fn reset_sets(
+    mut sets : ResMut<Sets>,
+) {
+    *sets = Sets::default();
+}
+
+/// Starts the next set: clears the court, resets the points and, with `GameConfig::switch_sides`,
+/// swaps the ends everything tied to a side is on. Runs on the tick so a replay switches on the
+/// same tick; only a live match shows the interstitial.
+pub fn switch_sides(
+    mut commands : Commands,
+    mut sets : ResMut<Sets>,
+    mut ball_count : ResMut<BallCount>,
+    mut state : ResMut<State<GameState>>,
+    exits : Res<ExitQueue>,
+    balls : Query<Entity, With<Ball>>,
+    mut scores : Query<(&mut Text, &mut Score)>,
+    mut sided : Query<&mut Transform, Or<(With<Paddle>, With<Score>, With<ComboText>)>>,
+    config : Res<GameConfig>,
+) {
+    if sets.ended.take().is_none() {
+        return;
+    }
+
+    if config.switch_sides {
+        sets.swapped = !sets.swapped;
+        for mut t in sided.iter_mut() {
+            t.translation.x = -t.translation.x;
+        }
+    }
+
+    // balls that went out this tick are already being despawned
+    for e in balls.iter() {
+        if !exits.contains(e) {
+            commands.entity(e).despawn();
+        }
+    }
+    ball_count.0 = 0;
+
+    for (mut text, mut score) in scores.iter_mut() {
+        // counters are keyed by the side balls go out on, so Who::PLAYER counts the bot's points
+        score.1 = match score.0 {
+            Who::PLAYER => config.bot_handicap.head_start,
+            Who::OPPONENT => config.player_handicap.head_start,
+        };
+        text.sections[0].value = score.1.to_string();
+    }
+
+    if *state.current() == GameState::Playing {
+        let _ = state.push(GameState::SwitchingSides);
+    }
+}
+
+pub struct SwitchTimer(Timer);
+
+impl Default for SwitchTimer {
+    fn default() -> Self {
+        Self(Timer::from_seconds(SWITCH_TIME, false))
+    }
+}
+
+pub fn spawn_switch_screen(
+    mut commands : Commands,
+    mut timer : ResMut<SwitchTimer>,
+    font : Res<UiFont>,
+    sets : Res<Sets>,
+    config : Res<GameConfig>,
+) {
+    let title = if config.switch_sides { "Switching sides" } else { "Next set" };
+    spawn_screen_text(&mut commands, &font, title, Vec2::new(0., 150.), 80.);
+    let line = format!("Sets: you {} - {} bot", sets.won(Who::PLAYER), sets.won(Who::OPPONENT));
+    spawn_screen_text(&mut commands, &font, &line, Vec2::new(0., 80.), 30.);
+    timer.0.reset();
+}
+
+pub fn finish_switch(
+    mut timer : ResMut<SwitchTimer>,
+    mut state : ResMut<State<GameState>>,
+    time : Res<Time>,
+) {
+    if timer.0.tick(time.delta()).just_finished() {
+        let _ = state.pop();
+    }
+}
+
+/// Shows "Deuce" or who has the advantage once both sides are a point off the limit.
+#[derive(Component)]
+pub struct DeuceText;
+
+pub fn spawn_deuce_text(
+    mut commands : Commands,
+    font : Res<UiFont>,
+    config : Res<GameConfig>,
+) {
+    commands.spawn_bundle(Text2dBundle {
+        text: Text::with_section(
+            "", TextStyle {
+                font: font.0.clone(),
+                font_size: 40.,
+                color: Color::YELLOW,
+            }, TextAlignment {
+                vertical: VerticalAlign::Center,
+                horizontal: HorizontalAlign::Center,
+            }),
+        transform: Transform::from_xyz(0., -config.court_half().y + DEUCE_MARGIN, DEUCE_Z),
+        ..Default::default()
+    })
+        .insert(DeuceText)
+        .insert(MatchEntity);
+}
+
+pub fn update_deuce_text(
+    scores : Query<&Score>,
+    mut text : Query<&mut Text, With<DeuceText>>,
+    config : Res<GameConfig>,
+) {
+    let (mut player, mut bot) = (0, 0);
+    // counters are keyed by the side balls go out on, the opposite of who they score for
+    for score in scores.iter() {
+        match score.0 {
+            Who::OPPONENT => player = score.1,
+            Who::PLAYER => bot = score.1,
+        }
+    }
+
+    let label = if !config.win_by_two || player.min(bot) < config.score_limit - 1 {
+        ""
+    } else if player == bot {
+        "Deuce"
+    } else if player == bot + 1 {
+        "Advantage you"
+    } else if bot == player + 1 {
+        "Advantage bot"
+    } else {
+        ""
+    };
+    for mut text in text.iter_mut() {
+        if text.sections[0].value != label {
+            text.sections[0].value = label.to_string();
+        }
+    }
+}
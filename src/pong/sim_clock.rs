@@ -0,0 +1,114 @@
+use bevy::{core::prelude::*, ecs::prelude::*, ecs::schedule::ShouldRun, input::prelude::*, log::info};
+
+use super::{TICK_RATE, config::{ConfigWatcher, GameConfig}};
+
+const SLOWER_KEY: KeyCode = KeyCode::LBracket;
+const FASTER_KEY: KeyCode = KeyCode::RBracket;
+const SPEEDS: [f32; 7] = [0.1, 0.25, 0.5, 1., 2., 3., 4.];
+/// Most physics ticks run in one frame, so a slow frame at high speed doesn't snowball.
+const MAX_TICKS_PER_FRAME: f64 = 8.;
+/// Most physics ticks run in one frame while fast-forwarding, so the window stays responsive.
+const MAX_FAST_FORWARD_PER_FRAME: u32 = 600;
+
+/// How fast simulated time passes relative to real time, from 0.1 to 4.
+pub struct SimulationSpeed(pub f32);
+
+impl Default for SimulationSpeed {
+    fn default() -> Self {
+        Self(1.)
+    }
+}
+
+/// Fixed timestep accumulator like `FixedTimestep`, but fed scaled time.
+pub struct SimClock {
+    /// Seconds per physics tick, from `GameConfig::tick_rate` when the match started.
+    pub timestep: f64,
+    accumulator: f64,
+    looping: bool,
+    /// Stops time from adding up; fast-forwarding still runs.
+    pub paused: bool,
+    /// Ticks to run as quickly as possible, regardless of time.
+    pub fast_forward: u32,
+    fast_forwarded: u32,
+}
+
+impl Default for SimClock {
+    fn default() -> Self {
+        Self {
+            timestep: 1. / TICK_RATE as f64,
+            accumulator: 0.,
+            looping: false,
+            paused: false,
+            fast_forward: 0,
+            fast_forwarded: 0,
+        }
+    }
+}
+
+/// Picks up the configured tick rate, switching to one reloaded from the config file since. Only
+/// done when a match starts, so a match never changes rate part way through.
+pub fn apply_tick_rate(
+    mut clock : ResMut<SimClock>,
+    mut config : ResMut<GameConfig>,
+    watcher : Option<ResMut<ConfigWatcher>>,
+) {
+    if let Some(rate) = watcher.and_then(|mut watcher| watcher.take_tick_rate()) {
+        config.tick_rate = rate;
+    }
+    clock.timestep = config.timestep();
+    clock.accumulator = 0.;
+}
+
+/// Run criteria for the physics tick: runs it as many times as the scaled frame time covers.
+pub fn fixed_tick(
+    mut clock : ResMut<SimClock>,
+    speed : Res<SimulationSpeed>,
+    time : Res<Time>,
+) -> ShouldRun {
+    // only the first check of a frame adds that frame's time
+    if !clock.looping {
+        if !clock.paused {
+            clock.accumulator += time.delta_seconds_f64() * speed.0 as f64;
+            clock.accumulator = clock.accumulator.min(clock.timestep * MAX_TICKS_PER_FRAME);
+        }
+        clock.fast_forwarded = 0;
+    }
+
+    if clock.fast_forward > 0 {
+        if clock.fast_forwarded == MAX_FAST_FORWARD_PER_FRAME {
+            // carry on next frame
+            clock.looping = false;
+            return ShouldRun::No;
+        }
+        clock.fast_forward -= 1;
+        clock.fast_forwarded += 1;
+        clock.looping = true;
+        return ShouldRun::YesAndCheckAgain;
+    }
+
+    if clock.accumulator >= clock.timestep {
+        clock.accumulator -= clock.timestep;
+        clock.looping = true;
+        ShouldRun::YesAndCheckAgain
+    } else {
+        clock.looping = false;
+        ShouldRun::No
+    }
+}
+
+pub fn simulation_speed_keys(
+    input : Res<Input<KeyCode>>,
+    mut speed : ResMut<SimulationSpeed>,
+) {
+    let delta = if input.just_pressed(SLOWER_KEY) {
+        -1
+    } else if input.just_pressed(FASTER_KEY) {
+        1
+    } else {
+        return;
+    };
+
+    let index = SPEEDS.iter().position(|s| *s >= speed.0).unwrap_or(SPEEDS.len() - 1) as i32;
+    speed.0 = SPEEDS[(index + delta).clamp(0, SPEEDS.len() as i32 - 1) as usize];
+    info!("simulation speed {}x", speed.0);
+}
@@ -0,0 +1,75 @@
+use bevy::{ecs::prelude::*, render::color::Color, text::prelude::*, transform::prelude::*};
+
+use super::{Ball, MatchEntity, UiFont, Velocity, config::GameConfig, sim_clock::SimClock};
+
+const MARGIN: f32 = 16.;
+const HUD_Z: f32 = 5.;
+const FONT_SIZE: f32 = 24.;
+
+/// Fastest ball in play right now and fastest of the match, in pixels per physics tick.
+#[derive(Default)]
+pub struct BallSpeeds {
+    pub current: f32,
+    pub record: f32,
+}
+
+#[derive(Component)]
+pub struct SpeedometerText;
+
+pub fn reset_ball_speeds(
+    mut speeds : ResMut<BallSpeeds>,
+) {
+    *speeds = BallSpeeds::default();
+}
+
+pub fn measure_ball_speeds(
+    mut speeds : ResMut<BallSpeeds>,
+    balls : Query<&Velocity, With<Ball>>,
+) {
+    let current = balls.iter().map(|v| v.0.length()).fold(0., f32::max);
+    if current != speeds.current {
+        speeds.current = current;
+        speeds.record = speeds.record.max(current);
+    }
+}
+
+/// Bottom right, clear of the minimap and the scores.
+pub fn spawn_speedometer(
+    mut commands : Commands,
+    font : Res<UiFont>,
+    config : Res<GameConfig>,
+) {
+    let court = config.court_half();
+    commands.spawn_bundle(Text2dBundle {
+        text: Text::with_section(
+            "", TextStyle {
+                font: font.0.clone(),
+                font_size: FONT_SIZE,
+                color: Color::WHITE,
+            }, TextAlignment {
+                vertical: VerticalAlign::Bottom,
+                horizontal: HorizontalAlign::Right,
+            }),
+        transform: Transform::from_xyz(court.x - MARGIN, -court.y + MARGIN, HUD_Z),
+        ..Default::default()
+    })
+        .insert(SpeedometerText)
+        .insert(MatchEntity);
+}
+
+/// Shows the speeds in pixels per second.
+pub fn update_speedometer(
+    speeds : Res<BallSpeeds>,
+    mut texts : Query<&mut Text, With<SpeedometerText>>,
+    clock : Res<SimClock>,
+) {
+    if !speeds.is_changed() {
+        return;
+    }
+    // the rate the physics actually runs at
+    let per_second = (1. / clock.timestep) as f32;
+    let value = format!("{:.0} px/s  (best {:.0})", speeds.current * per_second, speeds.record * per_second);
+    for mut text in texts.iter_mut() {
+        text.sections[0].value = value.clone();
+    }
+}
@@ -0,0 +1,42 @@
+use bevy::{core::prelude::*, ecs::prelude::*, math::Vec3, transform::prelude::*};
+
+/// Seconds a squash takes to ease back to the normal shape.
+const DURATION: f32 = 0.15;
+/// How much narrower along x (and taller along y) a paddle gets when hit.
+pub const PADDLE_SQUASH: f32 = 0.25;
+pub const BALL_SQUASH: f32 = 0.4;
+
+/// Squashes the sprite along x for a moment after a paddle hit. Only the transform's scale changes;
+/// collisions use the configured sizes, so the physics is unaffected.
+#[derive(Component, Default)]
+pub struct Squash {
+    amount: f32,
+    elapsed: f32,
+}
+
+impl Squash {
+    pub fn hit(&mut self, amount: f32) {
+        self.amount = amount;
+        self.elapsed = 0.;
+    }
+}
+
+pub fn animate_squash(
+    mut squashed : Query<(&mut Squash, &mut Transform)>,
+    time : Res<Time>,
+) {
+    for (mut squash, mut t) in squashed.iter_mut() {
+        if squash.amount == 0. {
+            continue;
+        }
+        squash.elapsed += time.delta_seconds();
+        let progress = (squash.elapsed / DURATION).min(1.);
+        // ease out, so most of the recovery happens straight after the hit
+        let amount = squash.amount * (1. - progress).powi(2);
+        t.scale = Vec3::new(1. - amount, 1. + amount, 1.);
+        if progress >= 1. {
+            squash.amount = 0.;
+            t.scale = Vec3::ONE;
+        }
+    }
+}
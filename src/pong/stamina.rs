@@ -0,0 +1,104 @@
+use bevy::{ecs::prelude::*, math::{Vec2, Vec3}, render::{color::Color, view::Visibility}, sprite, transform::prelude::*};
+
+use super::{BallSprite, MatchEntity, Player, Velocity, Who, config::GameConfig, sets::Sets};
+
+/// Stamina used per second of moving: a full bar lasts three seconds.
+const DRAIN: f32 = 1. / 3.;
+/// Stamina recovered per second of standing still.
+const REGEN: f32 = 1. / 2.;
+/// Fraction of the paddle speed left with an empty bar.
+const EXHAUSTED_SPEED: f32 = 0.4;
+const BAR_WIDTH: f32 = 200.;
+const BAR_HEIGHT: f32 = 8.;
+const BAR_MARGIN: f32 = 20.;
+const BAR_Z: f32 = 5.;
+
+/// Stamina of the player paddle from 0 to 1, only used when `GameConfig::stamina` is on.
+#[derive(Component)]
+pub struct Stamina(pub f32);
+
+impl Default for Stamina {
+    fn default() -> Self {
+        Self(1.)
+    }
+}
+
+impl Stamina {
+    /// Multiplier for the paddle speed.
+    pub fn speed_factor(&self, config: &GameConfig) -> f32 {
+        if config.stamina && self.0 <= 0. { EXHAUSTED_SPEED } else { 1. }
+    }
+}
+
+/// Slows the player paddle while its stamina is out. Runs in the physics tick over the full speed
+/// the live or replayed input set, so a replay slows the paddle on the same ticks the match did.
+pub fn apply_stamina(
+    mut players : Query<(&mut Velocity, &Stamina), With<Player>>,
+    config : Res<GameConfig>,
+) {
+    if !config.stamina {
+        return;
+    }
+
+    for (mut v, stamina) in players.iter_mut() {
+        if v.0.y != 0. {
+            // set rather than scaled, as live input is only read once for every tick of a frame
+            v.0.y = config.paddle_speed_of(true).copysign(v.0.y) * stamina.speed_factor(&config);
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct StaminaBar;
+
+pub fn update_stamina(
+    mut players : Query<(&Velocity, &mut Stamina), With<Player>>,
+    config : Res<GameConfig>,
+) {
+    if !config.stamina {
+        return;
+    }
+
+    for (v, mut stamina) in players.iter_mut() {
+        stamina.0 = if v.0.y != 0. {
+            (stamina.0 - config.per_tick(DRAIN)).max(0.)
+        } else {
+            (stamina.0 + config.per_tick(REGEN)).min(1.)
+        };
+    }
+}
+
+pub fn spawn_stamina_bar(
+    mut commands: Commands,
+    sprite: Res<BallSprite>,
+) {
+    // placed by update_stamina_bar
+    commands.spawn_bundle(sprite.bundle(Vec2::new(BAR_WIDTH, BAR_HEIGHT), Vec3::ZERO))
+        .insert(StaminaBar)
+        .insert(MatchEntity);
+}
+
+/// Draws the player's stamina along the top of their half of the court.
+pub fn update_stamina_bar(
+    players : Query<&Stamina, With<Player>>,
+    mut bar : Query<(&mut sprite::Sprite, &mut Transform, &mut Visibility), With<StaminaBar>>,
+    sets : Res<Sets>,
+    config : Res<GameConfig>,
+) {
+    let stamina = match players.iter().next() {
+        Some(stamina) => stamina.0,
+        None => return,
+    };
+
+    let court = config.court_half();
+    for (mut sprite, mut t, mut visibility) in bar.iter_mut() {
+        visibility.is_visible = config.stamina;
+        let width = BAR_WIDTH * stamina;
+        sprite.custom_size = Some(Vec2::new(width, BAR_HEIGHT));
+        sprite.color = if stamina <= 0. { Color::RED } else { Color::GREEN };
+        // shrinks towards the left edge
+        t.translation.x = sets.side_of(Who::PLAYER) * court.x / 2. - (BAR_WIDTH - width) / 2.;
+        t.translation.y = court.y - BAR_MARGIN;
+        t.translation.z = BAR_Z;
+    }
+}
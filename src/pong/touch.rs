@@ -0,0 +1,81 @@
+use bevy::{ecs::prelude::*, input::prelude::*, math::{Vec2, Vec3}, render::{color::Color, view::Visibility}, sprite, transform::prelude::*, window::prelude::*};
+
+use super::{BallSprite, MatchEntity, Who, config::GameConfig, sandbox, sets::Sets};
+
+/// Below the balls and paddles, above the court.
+const ZONE_Z: f32 = -0.5;
+const IDLE_ALPHA: f32 = 0.04;
+const HELD_ALPHA: f32 = 0.12;
+
+/// Set once the first touch is seen; the touch zones are only drawn from then on.
+#[derive(Default)]
+pub struct TouchDetected(pub bool);
+
+pub fn detect_touch(
+    touches : Res<Touches>,
+    mut detected : ResMut<TouchDetected>,
+) {
+    if !detected.0 && touches.iter().next().is_some() {
+        detected.0 = true;
+    }
+}
+
+/// Where the first finger still on the screen is, in world units.
+pub fn held_touch(touches: &Touches, window: &Window, config: &GameConfig) -> Option<Vec2> {
+    touches.iter().next().map(|touch| sandbox::window_to_world(window, touch.position(), config))
+}
+
+/// The upper (1) or lower (-1) half of the screen; holding a finger on it moves the paddle that
+/// way. Only drawn over the player's end, but a finger on the other end counts too.
+#[derive(Component)]
+pub struct TouchZone(i8);
+
+pub fn spawn_touch_zones(
+    mut commands : Commands,
+    sprite : Res<BallSprite>,
+) {
+    for direction in [1, -1] {
+        // placed and sized by update_touch_zones
+        let mut zone = sprite.tinted(Color::rgba(1., 1., 1., IDLE_ALPHA), Vec2::ZERO, Vec3::new(0., 0., ZONE_Z));
+        zone.visibility = Visibility { is_visible: false };
+        commands.spawn_bundle(zone)
+            .insert(TouchZone(direction))
+            .insert(MatchEntity);
+    }
+}
+
+/// Shows the zones on the player's end once a touch has been seen, lighting up the one held.
+pub fn update_touch_zones(
+    mut zones : Query<(&TouchZone, &mut sprite::Sprite, &mut Transform, &mut Visibility)>,
+    touches : Res<Touches>,
+    detected : Res<TouchDetected>,
+    windows : Res<Windows>,
+    sets : Res<Sets>,
+    config : Res<GameConfig>,
+) {
+    let visible = detected.0 && config.input.player.uses_touch();
+    let court = config.court_half();
+    let held = windows.get_primary().and_then(|window| held_touch(&touches, window, &config));
+
+    for (zone, mut sprite, mut t, mut visibility) in zones.iter_mut() {
+        if visibility.is_visible != visible {
+            visibility.is_visible = visible;
+        }
+        if !visible {
+            continue;
+        }
+        let alpha = match held {
+            Some(touch) if controls_direction(touch) == zone.0 => HELD_ALPHA,
+            _ => IDLE_ALPHA,
+        };
+        sprite.color = Color::rgba(1., 1., 1., alpha);
+        sprite.custom_size = Some(court);
+        t.translation.x = sets.side_of(Who::PLAYER) * court.x / 2.;
+        t.translation.y = zone.0 as f32 * court.y / 2.;
+    }
+}
+
+/// Which way a finger at `touch` moves the paddle.
+pub fn controls_direction(touch: Vec2) -> i8 {
+    if touch.y >= 0. { 1 } else { -1 }
+}
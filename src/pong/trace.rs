@@ -0,0 +1,84 @@
+use std::{fs, time::{Duration, Instant, SystemTime, UNIX_EPOCH}};
+
+use bevy::{ecs::prelude::*, input::prelude::*, log::{info, warn}};
+
+use super::{profiler::SystemTimings, replay};
+
+const CAPTURE_KEY: KeyCode = KeyCode::F4;
+/// Seconds of spans a capture covers.
+const CAPTURE_SECONDS: u64 = 5;
+pub const TRACE_DIR: &str = "traces";
+
+struct Span {
+    name: &'static str,
+    start: Instant,
+    duration: Duration,
+}
+
+/// Spans recorded while a capture is running, written out as a `chrome://tracing` file when it ends.
+#[derive(Default)]
+pub struct TraceCapture {
+    started: Option<Instant>,
+    last_frame: Option<Instant>,
+    spans: Vec<Span>,
+}
+
+impl TraceCapture {
+    pub fn record(&mut self, name: &'static str, start: Instant, duration: Duration) {
+        if self.started.is_some() {
+            self.spans.push(Span { name, start, duration });
+        }
+    }
+
+    /// The spans in the Chrome trace event format, with times in microseconds from the start of the capture.
+    fn to_json(&self, origin: Instant) -> String {
+        let events: Vec<String> = self.spans.iter()
+            .map(|span| format!(
+                "{{\"name\":\"{}\",\"ph\":\"X\",\"ts\":{},\"dur\":{},\"pid\":0,\"tid\":0}}",
+                span.name,
+                span.start.saturating_duration_since(origin).as_micros(),
+                span.duration.as_micros(),
+            ))
+            .collect();
+        format!("{{\"traceEvents\":[\n{}\n]}}\n", events.join(",\n"))
+    }
+}
+
+fn save_trace(json: &str) -> Result<String, String> {
+    fs::create_dir_all(TRACE_DIR).map_err(|e| e.to_string())?;
+    let secs = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default();
+    let path = format!("{}/trace_{}.json", TRACE_DIR, replay::timestamp(secs));
+    fs::write(&path, json).map_err(|e| e.to_string())?;
+    Ok(path)
+}
+
+/// F4 starts a capture; after `CAPTURE_SECONDS` it is written to `TRACE_DIR`. Each frame gets a span of its own,
+/// so the system spans can be told apart per frame.
+pub fn capture_trace(
+    input : Res<Input<KeyCode>>,
+    mut timings : ResMut<SystemTimings>,
+) {
+    let now = Instant::now();
+    let capture = &mut timings.capture;
+
+    if let Some(last) = capture.last_frame.replace(now) {
+        capture.record("frame", last, now - last);
+    }
+
+    match capture.started {
+        None if input.just_pressed(CAPTURE_KEY) => {
+            info!("capturing {} seconds of trace", CAPTURE_SECONDS);
+            capture.started = Some(now);
+            capture.spans.clear();
+        }
+        Some(started) if now - started >= Duration::from_secs(CAPTURE_SECONDS) => {
+            match save_trace(&capture.to_json(started)) {
+                Ok(path) => info!("trace saved to {}", path),
+                Err(e) => warn!("couldn't save trace: {}", e),
+            }
+            capture.started = None;
+            capture.spans.clear();
+        }
+        _ => {}
+    }
+}
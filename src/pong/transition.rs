@@ -0,0 +1,150 @@
+use bevy::{app::prelude::*, core::prelude::*, ecs::prelude::*, math::Vec2, render::{color::Color, view::Visibility}, sprite::{self, SpriteBundle}, transform::prelude::*, window::prelude::*};
+
+use super::{BallSprite, GameState, config::GameConfig};
+
+const TRANSITION_TIME: f32 = 0.35;
+const OVERLAY_Z: f32 = 100.0;
+
+/// Covers the screen while switching between game states instead of swapping them instantly.
+pub struct TransitionPlugin;
+
+impl Plugin for TransitionPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Transition::default())
+            .add_startup_system_to_stage("game_setup", spawn_overlay)
+            .add_system(animate_transition);
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum TransitionStyle { Fade, Slide }
+
+#[derive(PartialEq)]
+enum Phase { Idle, Covering, Revealing }
+
+pub struct Transition {
+    target: Option<GameState>,
+    style: TransitionStyle,
+    phase: Phase,
+    timer: Timer,
+}
+
+impl Default for Transition {
+    fn default() -> Self {
+        Self {
+            target: None,
+            style: TransitionStyle::Fade,
+            phase: Phase::Idle,
+            timer: Timer::from_seconds(TRANSITION_TIME, false),
+        }
+    }
+}
+
+impl Transition {
+    pub fn fade_to(&mut self, state: GameState) {
+        self.start(state, TransitionStyle::Fade);
+    }
+
+    pub fn slide_to(&mut self, state: GameState) {
+        self.start(state, TransitionStyle::Slide);
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.phase != Phase::Idle
+    }
+
+    /// Requests made while another transition is running are ignored.
+    fn start(&mut self, state: GameState, style: TransitionStyle) {
+        if self.is_running() {
+            return;
+        }
+        self.target = Some(state);
+        self.style = style;
+        self.phase = Phase::Covering;
+        self.timer.reset();
+    }
+}
+
+#[derive(Component)]
+struct TransitionOverlay;
+
+fn spawn_overlay(
+    mut commands: Commands,
+    sprite: Res<BallSprite>,
+) {
+    commands.spawn_bundle(SpriteBundle {
+        sprite: sprite::Sprite {
+            color: Color::rgba(0., 0., 0., 0.),
+            ..Default::default()
+        },
+        texture: sprite.texture.clone(),
+        transform: Transform::from_xyz(0., 0., OVERLAY_Z),
+        visibility: Visibility { is_visible: false },
+        ..Default::default()
+    })
+        .insert(TransitionOverlay);
+}
+
+fn animate_transition(
+    mut transition: ResMut<Transition>,
+    mut state: ResMut<State<GameState>>,
+    mut overlay: Query<(&mut sprite::Sprite, &mut Transform, &mut Visibility), With<TransitionOverlay>>,
+    windows: Res<Windows>,
+    config: Res<GameConfig>,
+    time: Res<Time>,
+) {
+    if !transition.is_running() {
+        return;
+    }
+
+    // cover the whole view, letterbox included, not just the court
+    let window = windows.get_primary().unwrap();
+    let size = Vec2::new(window.width(), window.height()) * config.view_scale(window);
+
+    let progress = transition.timer.tick(time.delta()).percent();
+    let finished = transition.timer.finished();
+
+    // coverage goes 0 -> 1 while covering and 1 -> 0 while revealing
+    let coverage = match transition.phase {
+        Phase::Covering => progress,
+        _ => 1. - progress,
+    };
+
+    for (mut sprite, mut t, mut visibility) in overlay.iter_mut() {
+        visibility.is_visible = true;
+        sprite.custom_size = Some(size);
+        match transition.style {
+            TransitionStyle::Fade => {
+                sprite.color.set_a(coverage);
+                t.translation.x = 0.;
+            }
+            TransitionStyle::Slide => {
+                sprite.color.set_a(1.);
+                // slide in from the right, then carry on out to the left
+                t.translation.x = match transition.phase {
+                    Phase::Covering => size.x * (1. - coverage),
+                    _ => -size.x * (1. - coverage),
+                };
+            }
+        }
+        if finished && transition.phase == Phase::Revealing {
+            visibility.is_visible = false;
+        }
+    }
+
+    if !finished {
+        return;
+    }
+
+    match transition.phase {
+        Phase::Covering => {
+            if let Some(target) = transition.target.take() {
+                // replace the whole stack so leaving a pushed state (e.g. Paused) exits the match under it too
+                let _ = state.replace(target);
+            }
+            transition.phase = Phase::Revealing;
+            transition.timer.reset();
+        }
+        _ => transition.phase = Phase::Idle,
+    }
+}
@@ -0,0 +1,93 @@
+use bevy::{asset::prelude::*, ecs::prelude::*, math::Vec3, text::prelude::*, transform::prelude::*, window::{prelude::*, WindowResized, WindowScaleFactorChanged}};
+
+use super::{UiFont, config::GameConfig};
+
+/// Bounds on `UiScale`, for tiny or minimized windows and very large ones.
+const MIN_UI_SCALE: f32 = 0.25;
+const MAX_UI_SCALE: f32 = 8.;
+
+/// Screen pixels per world unit. Texts live in the world, so the camera already keeps them in
+/// proportion to the court; rasterizing them at this many times their size and shrinking them back
+/// keeps them sharp from 720p to 4K instead of stretching 720p-sized glyphs.
+pub struct UiScale(pub f32);
+
+impl Default for UiScale {
+    fn default() -> Self {
+        Self(1.)
+    }
+}
+
+/// Keeps `UiScale` in step with the camera, which is refitted on resizes and DPI changes.
+pub fn update_ui_scale(
+    mut resized : EventReader<WindowResized>,
+    mut rescaled : EventReader<WindowScaleFactorChanged>,
+    mut ui_scale : ResMut<UiScale>,
+    windows : Res<Windows>,
+    config : Res<GameConfig>,
+) {
+    let resized = resized.iter().count() > 0;
+    let rescaled = rescaled.iter().count() > 0;
+    if !resized && !rescaled && !config.is_changed() {
+        return;
+    }
+    let window = windows.get_primary().unwrap();
+    let scale = (1. / config.view_scale(window)).clamp(MIN_UI_SCALE, MAX_UI_SCALE);
+    if ui_scale.0 != scale {
+        ui_scale.0 = scale;
+    }
+}
+
+/// Keeps every text at `GameConfig::font_scale` times the size it was spawned with, rasterized at
+/// `UiScale`. Texts are spawned at their unscaled size, so new ones are scaled as they show up and
+/// existing ones rescaled whenever either scale changes. Runs in its own stage after Update, so it
+/// sees texts spawned there before they are first laid out.
+pub fn scale_text(
+    mut texts : Query<(&mut Text, &mut Transform, ChangeTrackers<Text>)>,
+    mut applied : Local<Option<f32>>,
+    ui_scale : Res<UiScale>,
+    config : Res<GameConfig>,
+) {
+    let scale = config.font_scale() * ui_scale.0;
+    let previous = applied.unwrap_or(1.);
+    *applied = Some(scale);
+
+    for (mut text, mut t, tracker) in texts.iter_mut() {
+        let factor = if tracker.is_added() { scale } else { scale / previous };
+        if factor != 1. {
+            for section in text.sections.iter_mut() {
+                section.style.font_size *= factor;
+            }
+        }
+        let shrink = Vec3::new(1. / ui_scale.0, 1. / ui_scale.0, 1.);
+        if t.scale != shrink {
+            t.scale = shrink;
+        }
+    }
+}
+
+/// Swaps every text over to `GameConfig::font` when it is changed in the config.
+pub fn reload_font(
+    mut font : ResMut<UiFont>,
+    mut texts : Query<&mut Text>,
+    mut loaded : Local<String>,
+    asset_server : Res<AssetServer>,
+    config : Res<GameConfig>,
+) {
+    if *loaded == config.font {
+        return;
+    }
+    let first = loaded.is_empty();
+    *loaded = config.font.clone();
+    // setup already loaded the font the config started with
+    if first {
+        return;
+    }
+
+    let old = font.0.clone();
+    font.0 = asset_server.load(config.font.as_str());
+    for mut text in texts.iter_mut() {
+        for section in text.sections.iter_mut().filter(|section| section.style.font == old) {
+            section.style.font = font.0.clone();
+        }
+    }
+}
@@ -0,0 +1,175 @@
+use bevy::math::Vec2;
+use powder_game::pong::{components::PaddleShape, physics, resources::GameConfig};
+use proptest::prelude::*;
+
+/// Half the default paddle and ball sizes.
+const PADDLE_HALF_WIDTH: f32 = 6.25;
+const PADDLE_HALF_HEIGHT: f32 = 400.;
+const BALL_HALF: f32 = 5.;
+/// Steepest a paddle face tilts, as `tan` of the tilt angle (the convex face at its ends).
+const MAX_TILT: f32 = 0.6;
+const EPSILON: f32 = 1e-3;
+
+fn shape() -> impl Strategy<Value = PaddleShape> {
+    prop_oneof![
+        Just(PaddleShape::Classic),
+        Just(PaddleShape::Flat),
+        Just(PaddleShape::Convex),
+        Just(PaddleShape::VShape),
+    ]
+}
+
+fn facing() -> impl Strategy<Value = f32> {
+    prop_oneof![Just(-1f32), Just(1f32)]
+}
+
+/// A ball overlapping the paddle on the side facing the court, relative to the paddle centre.
+fn hit(facing: f32) -> impl Strategy<Value = Vec2> {
+    (0.01f32..PADDLE_HALF_WIDTH + BALL_HALF, -(PADDLE_HALF_HEIGHT + BALL_HALF)..PADDLE_HALF_HEIGHT + BALL_HALF)
+        .prop_map(move |(x, y)| Vec2::new(x * facing, y))
+}
+
+/// A ball overlapping the paddle anywhere, relative to the paddle centre.
+fn overlap() -> impl Strategy<Value = Vec2> {
+    let reach = Vec2::new(PADDLE_HALF_WIDTH, PADDLE_HALF_HEIGHT) + Vec2::splat(BALL_HALF);
+    (-reach.x + EPSILON..reach.x - EPSILON, -reach.y + EPSILON..reach.y - EPSILON).prop_map(|(x, y)| Vec2::new(x, y))
+}
+
+/// A velocity heading into a paddle facing `facing`.
+fn incoming(facing: f32) -> impl Strategy<Value = Vec2> {
+    (0.1f32..20., -20f32..20.).prop_map(move |(x, y)| Vec2::new(-x * facing, y))
+}
+
+proptest! {
+    #[test]
+    fn reflection_preserves_speed(
+        shape in shape(),
+        offset in -1f32..1.,
+        facing in facing(),
+        v in (-50f32..50., -50f32..50.),
+    ) {
+        let v = Vec2::new(v.0, v.1);
+        let normal = physics::paddle_normal(shape, offset, facing);
+        let reflected = physics::reflect(v, normal);
+        prop_assert!((reflected.length() - v.length()).abs() <= EPSILON * v.length().max(1.));
+    }
+
+    #[test]
+    fn bounce_direction_is_a_unit_vector(
+        (facing, from_center, velocity) in facing().prop_flat_map(|f| (Just(f), hit(f), incoming(f))),
+        shape in shape(),
+    ) {
+        if let Some(direction) = physics::bounce_direction(shape, velocity, from_center, PADDLE_HALF_HEIGHT, facing) {
+            prop_assert!((direction.length() - 1.).abs() <= EPSILON);
+        }
+    }
+
+    #[test]
+    fn paddle_normal_stays_within_tilt_cone(
+        shape in shape(),
+        offset in -2f32..2.,
+        facing in facing(),
+    ) {
+        let normal = physics::paddle_normal(shape, offset, facing);
+        prop_assert!(normal.x * facing > 0.);
+        prop_assert!(normal.y.abs() / normal.x.abs() <= MAX_TILT + EPSILON);
+    }
+
+    #[test]
+    fn classic_bounce_leaves_along_hit_offset(
+        (facing, from_center) in facing().prop_flat_map(|f| (Just(f), hit(f))),
+        velocity in (-20f32..20., -20f32..20.),
+    ) {
+        let velocity = Vec2::new(velocity.0, velocity.1);
+        let direction = physics::bounce_direction(PaddleShape::Classic, velocity, from_center, PADDLE_HALF_HEIGHT, facing).unwrap();
+        prop_assert!(direction.x * facing > 0.);
+        prop_assert!(direction.y * from_center.y >= 0.);
+        // no steeper than the corner of the overlap allows
+        let steepest = (PADDLE_HALF_HEIGHT + BALL_HALF) / from_center.x.abs();
+        prop_assert!(direction.y.abs() / direction.x.abs() <= steepest + EPSILON);
+    }
+
+    /// Wherever the ball has sunk into the paddle, resolving the hit sends it out of the face, clear
+    /// of the paddle after the next tick's move.
+    #[test]
+    fn resolved_ball_heads_out_of_the_paddle(
+        (facing, overlapping, velocity) in facing().prop_flat_map(|f| (Just(f), overlap(), incoming(f))),
+        shape in shape(),
+        speed in 1f32..30.,
+    ) {
+        let paddle_half = Vec2::new(PADDLE_HALF_WIDTH, PADDLE_HALF_HEIGHT);
+        let from_center = physics::resolve_paddle_hit(overlapping, BALL_HALF, paddle_half, facing);
+        prop_assert!(from_center.x * facing >= overlapping.x * facing);
+        prop_assert_eq!(from_center.y, overlapping.y);
+        let direction = match physics::bounce_direction(shape, velocity, from_center, PADDLE_HALF_HEIGHT, facing) {
+            Some(direction) => direction,
+            None => return Ok(()),
+        };
+        prop_assert!(direction.x * facing >= 0.);
+
+        let position = from_center + direction * speed;
+        prop_assert!(!physics::overlaps(position, Vec2::splat(BALL_HALF), Vec2::ZERO, paddle_half));
+    }
+}
+
+#[test]
+fn ball_on_the_paddle_centre_goes_straight_out() {
+    for facing in [-1., 1.] {
+        let direction = physics::bounce_direction(PaddleShape::Classic, Vec2::new(-facing, 0.), Vec2::ZERO, PADDLE_HALF_HEIGHT, facing).unwrap();
+        assert_eq!(direction, Vec2::new(facing, 0.));
+    }
+}
+
+#[test]
+fn degenerate_vectors_normalize_to_the_fallback() {
+    for v in [Vec2::ZERO, Vec2::splat(1e-9), Vec2::new(f32::INFINITY, 0.), Vec2::new(f32::NAN, 1.)] {
+        assert_eq!(physics::normalize_or(v, Vec2::X), Vec2::X);
+    }
+    assert_eq!(physics::normalize_or(Vec2::new(0., -3.), Vec2::X), Vec2::new(0., -1.));
+}
+
+proptest! {
+    #[test]
+    fn limited_velocity_crosses_the_court_without_speeding_up(
+        v in (-30f32..30., -30f32..30.),
+        min_x in 0f32..10.,
+    ) {
+        let v = Vec2::new(v.0, v.1);
+        let limited = physics::limit_velocity(v, min_x, f32::INFINITY);
+        prop_assert!((limited.length() - v.length()).abs() <= EPSILON * v.length().max(1.));
+        prop_assert!(limited.x.abs() + EPSILON >= min_x.min(v.length()));
+        prop_assert!(limited.x * v.x >= 0. && limited.y * v.y >= 0.);
+    }
+
+    #[test]
+    fn limited_velocity_stays_under_the_cap(
+        v in (-30f32..30., -30f32..30.),
+        max_speed in 1f32..20.,
+    ) {
+        let limited = physics::limit_velocity(Vec2::new(v.0, v.1), 2., max_speed);
+        prop_assert!(limited.length() <= max_speed + EPSILON);
+    }
+
+    /// The limits are configured per second and applied to velocities per tick.
+    #[test]
+    fn ball_velocity_is_held_to_the_configured_limits(
+        v in (-100f32..100., -100f32..100.),
+        tick_rate in 30u32..240,
+    ) {
+        let config = GameConfig { tick_rate, ..GameConfig::default() };
+        let v = Vec2::new(v.0, v.1);
+        let limited = config.limit_ball_velocity(v);
+        let (min_x, max_speed) = (config.per_tick(config.min_ball_speed_x), config.per_tick(config.max_ball_speed));
+        prop_assert!(limited.length() <= max_speed + EPSILON);
+        prop_assert!(limited.x.abs() + EPSILON >= min_x.min(v.length().min(max_speed)));
+        prop_assert!(limited.x * v.x >= 0. && limited.y * v.y >= 0.);
+    }
+}
+
+#[test]
+fn zero_ball_speed_limits_leave_the_velocity_alone() {
+    let config = GameConfig { min_ball_speed_x: 0., max_ball_speed: 0., ..GameConfig::default() };
+    for v in [Vec2::new(0.1, 30.), Vec2::new(-500., 2.), Vec2::ZERO] {
+        assert_eq!(config.limit_ball_velocity(v), v);
+    }
+}
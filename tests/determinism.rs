@@ -0,0 +1,59 @@
+use std::thread;
+
+use powder_game::pong::{resources::GameConfig, simulation::{Simulation, first_divergence, scripted_replay}};
+
+const TICKS: u32 = 1800;
+
+fn config() -> GameConfig {
+    GameConfig {
+        balls_amount: 5,
+        // serve early so most of the run has balls in play
+        ball_launch_time: 1.,
+        ..GameConfig::default()
+    }
+}
+
+#[test]
+fn same_seed_simulates_the_same() {
+    for seed in [0, 1, 12345] {
+        let replay = scripted_replay(&config(), seed, TICKS);
+        let a = Simulation::checksums(config(), replay.clone());
+        let b = Simulation::checksums(config(), replay);
+        assert_eq!(a.len(), TICKS as usize);
+        assert_eq!(first_divergence(&a, &b), None, "seed {} diverged", seed);
+    }
+}
+
+/// Stamina slows the paddle inside the physics tick, so it replays the same as well.
+#[test]
+fn same_seed_with_stamina_simulates_the_same() {
+    let config = || GameConfig { stamina: true, ..config() };
+    for seed in [0, 3] {
+        let replay = scripted_replay(&config(), seed, TICKS);
+        let a = Simulation::checksums(config(), replay.clone());
+        let b = Simulation::checksums(config(), replay);
+        assert_eq!(first_divergence(&a, &b), None, "seed {} diverged with stamina on", seed);
+    }
+}
+
+#[test]
+fn runs_on_other_threads_simulate_the_same() {
+    let replay = scripted_replay(&config(), 7, TICKS);
+    let here = Simulation::checksums(config(), replay.clone());
+    let handles: Vec<_> = (0..2)
+        .map(|_| {
+            let replay = replay.clone();
+            thread::spawn(move || Simulation::checksums(config(), replay))
+        })
+        .collect();
+    for handle in handles {
+        assert_eq!(first_divergence(&here, &handle.join().unwrap()), None);
+    }
+}
+
+#[test]
+fn different_seeds_diverge() {
+    let a = Simulation::checksums(config(), scripted_replay(&config(), 1, TICKS));
+    let b = Simulation::checksums(config(), scripted_replay(&config(), 2, TICKS));
+    assert!(first_divergence(&a, &b).is_some());
+}
@@ -0,0 +1,60 @@
+use bevy::ecs::world::World;
+use powder_game::pong::{components::Who, events::{ExitQueue, ExitReader, ExitScreenEvent}};
+
+#[test]
+fn every_reader_takes_each_exit_once() {
+    let mut world = World::new();
+    let (a, b) = (world.spawn().id(), world.spawn().id());
+    let mut exits = ExitQueue::default();
+    exits.push(a, Who::PLAYER);
+    exits.push(b, Who::OPPONENT);
+
+    assert_eq!(exits.take(ExitReader::Despawn), vec![ExitScreenEvent(a, Who::PLAYER), ExitScreenEvent(b, Who::OPPONENT)]);
+    assert!(exits.take(ExitReader::Despawn).is_empty());
+    assert_eq!(exits.take(ExitReader::Score).len(), 2);
+    assert!(exits.take(ExitReader::Score).is_empty());
+}
+
+#[test]
+fn exits_wait_for_a_reader_running_on_a_later_tick() {
+    let mut world = World::new();
+    let ball = world.spawn().id();
+    let mut exits = ExitQueue::default();
+    exits.push(ball, Who::PLAYER);
+
+    exits.take(ExitReader::Despawn);
+    // the scoring side hasn't run yet, so several retirements later the exit is still there
+    for _ in 0..3 {
+        exits.retire();
+    }
+    assert_eq!(exits.take(ExitReader::Score), vec![ExitScreenEvent(ball, Who::PLAYER)]);
+    exits.retire();
+    assert!(exits.is_empty());
+}
+
+#[test]
+fn a_ball_is_only_queued_once() {
+    let mut world = World::new();
+    let ball = world.spawn().id();
+    let mut exits = ExitQueue::default();
+    // still overlapping the goal on the ticks before its despawn applies
+    exits.push(ball, Who::OPPONENT);
+    exits.push(ball, Who::OPPONENT);
+    exits.take(ExitReader::Score);
+    exits.push(ball, Who::OPPONENT);
+
+    assert_eq!(exits.len(), 1);
+    assert!(exits.take(ExitReader::Score).is_empty());
+}
+
+#[test]
+fn clearing_drops_exits_nobody_took() {
+    let mut world = World::new();
+    let ball = world.spawn().id();
+    let mut exits = ExitQueue::default();
+    exits.push(ball, Who::PLAYER);
+    exits.clear();
+
+    assert!(exits.take(ExitReader::Score).is_empty());
+    assert!(exits.take(ExitReader::Despawn).is_empty());
+}
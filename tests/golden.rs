@@ -0,0 +1,75 @@
+//! Re-simulates the replays in `tests/golden/` and compares the outcome with the `.expected.ron`
+//! next to each one, to catch physics changes nobody meant to make.
+//!
+//! The `synthetic_*` replays are hand-written input scripts rather than recorded matches, so their
+//! headers carry `config_hash: 0`. Every replay is played with `golden_config`, whatever its header
+//! says. After an intended physics change, run with `UPDATE_GOLDEN=1` to record new outcomes; a
+//! replay without an expected outcome fails the test until one is recorded that way.
+
+use std::{env, fs, path::Path};
+
+use powder_game::pong::{resources::GameConfig, simulation::{Replay, Simulation}};
+use serde::{Deserialize, Serialize};
+
+const GOLDEN_DIR: &str = "tests/golden";
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Outcome {
+    /// `(player, bot)` points at the end.
+    scores: (i32, i32),
+    /// `Simulation::checksum` after the last tick.
+    checksum: u64,
+}
+
+fn golden_config() -> GameConfig {
+    GameConfig {
+        balls_amount: 3,
+        ..GameConfig::default()
+    }
+}
+
+fn simulate(replay: Replay) -> Outcome {
+    let length = replay.header.length;
+    let mut simulation = Simulation::new(golden_config(), replay);
+    while simulation.tick() < length {
+        simulation.step();
+    }
+    Outcome { scores: simulation.scores(), checksum: simulation.checksum() }
+}
+
+#[test]
+fn golden_replays_play_back_unchanged() {
+    let update = env::var_os("UPDATE_GOLDEN").is_some();
+    let mut replays: Vec<_> = fs::read_dir(GOLDEN_DIR).unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.to_string_lossy().ends_with(".ron") && !path.to_string_lossy().ends_with(".expected.ron"))
+        .collect();
+    replays.sort();
+    assert!(!replays.is_empty(), "no replays in {}", GOLDEN_DIR);
+
+    let mut mismatches = Vec::new();
+    let mut missing = Vec::new();
+    for path in replays.iter() {
+        let replay = Replay::load(&path.to_string_lossy()).unwrap();
+        let outcome = simulate(replay);
+        let expected_path = path.with_extension("expected.ron");
+
+        if update {
+            let data = ron::ser::to_string_pretty(&outcome, Default::default()).unwrap();
+            fs::write(&expected_path, data).unwrap();
+            eprintln!("recorded {}", expected_path.display());
+            continue;
+        }
+        if !Path::new(&expected_path).exists() {
+            missing.push(expected_path.display().to_string());
+            continue;
+        }
+
+        let expected: Outcome = ron::from_str(&fs::read_to_string(&expected_path).unwrap()).unwrap();
+        if outcome != expected {
+            mismatches.push(format!("{}: expected {:?}, got {:?}", path.display(), expected, outcome));
+        }
+    }
+    assert!(missing.is_empty(), "no expected outcome, run with UPDATE_GOLDEN=1 to record it:\n{}", missing.join("\n"));
+    assert!(mismatches.is_empty(), "replays played back differently:\n{}", mismatches.join("\n"));
+}
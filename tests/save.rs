@@ -0,0 +1,52 @@
+//! A saved match, loaded back, has to carry on exactly like the match it was saved from.
+
+use std::{env, fs, process};
+
+use powder_game::pong::{resources::GameConfig, simulation::{Simulation, first_divergence, scripted_replay}};
+
+/// Ticks to save on: before the first serve, and mid-rally.
+const SAVE_TICKS: [u32; 3] = [30, 777, 1500];
+const TICKS_AFTER: u32 = 900;
+
+/// A single ball, so reloading can't change the order balls are iterated and their serves rolled in.
+fn config() -> GameConfig {
+    GameConfig {
+        balls_amount: 1,
+        ball_launch_time: 1.,
+        ..GameConfig::default()
+    }
+}
+
+#[test]
+fn loaded_match_carries_on_like_the_saved_one() {
+    let path = env::temp_dir().join(format!("pong-save-round-trip-{}.ron", process::id()));
+    let path = path.to_string_lossy().into_owned();
+
+    for save_tick in SAVE_TICKS {
+        let replay = scripted_replay(&config(), 9, save_tick + TICKS_AFTER);
+        let mut saved = Simulation::new(config(), replay.clone());
+        while saved.tick() < save_tick {
+            saved.step();
+        }
+        saved.save(&path);
+
+        let mut loaded = Simulation::new(config(), replay);
+        loaded.step();
+        loaded.load(&path);
+        assert_eq!(loaded.tick(), save_tick);
+        assert_eq!(loaded.scores(), saved.scores());
+        assert_eq!(loaded.checksum(), saved.checksum(), "loading the save from tick {} changed the match", save_tick);
+
+        let mut expected = Vec::with_capacity(TICKS_AFTER as usize);
+        let mut actual = Vec::with_capacity(TICKS_AFTER as usize);
+        for _ in 0..TICKS_AFTER {
+            saved.step();
+            expected.push(saved.checksum());
+            loaded.step();
+            actual.push(loaded.checksum());
+        }
+        assert_eq!(first_divergence(&expected, &actual), None, "match loaded from tick {} diverged", save_tick);
+    }
+
+    let _ = fs::remove_file(&path);
+}